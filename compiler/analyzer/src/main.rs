@@ -1,5 +1,5 @@
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 
@@ -19,6 +19,7 @@ enum Node {
         name: String, 
         params: Vec<Param>, 
         #[serde(rename = "returnType")] returnType: String, 
+        #[serde(rename = "returnLifetime", default)] returnLifetime: Option<String>,
         body: Box<Node>, 
         position: Option<Pos> 
     },
@@ -29,10 +30,12 @@ enum Node {
     MemberExpression { object: Box<Node>, property: String, position: Option<Pos> },
     BinaryExpression { operator: String, left: Box<Node>, right: Box<Node>, position: Option<Pos> },
     IfStatement { test: Box<Node>, consequent: Box<Node>, alternate: Option<Box<Node>>, position: Option<Pos> },
+    ConditionalExpression { test: Box<Node>, consequent: Box<Node>, alternate: Box<Node>, position: Option<Pos> },
     WhileStatement { test: Box<Node>, body: Box<Node>, position: Option<Pos> },
     ForStatement { init: Option<Box<Node>>, test: Option<Box<Node>>, update: Option<Box<Node>>, body: Box<Node>, position: Option<Pos> },
-    BreakStatement { position: Option<Pos> },
+    BreakStatement { argument: Option<Box<Node>>, position: Option<Pos> },
     ContinueStatement { position: Option<Pos> },
+    UnaryExpression { operator: String, argument: Box<Node> },
     Identifier { name: String, position: Option<Pos> },
     Literal { value: serde_json::Value, position: Option<Pos> },
     ReturnStatement { argument: Option<Box<Node>>, position: Option<Pos> },
@@ -40,10 +43,33 @@ enum Node {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct Param { name: String, #[serde(rename = "type")] param_type: String }
+struct Param { name: String, #[serde(rename = "type")] param_type: String, #[serde(default)] lifetime: Option<String> }
+
+/// How a call uses a variable passed as an argument.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum UseKind {
+    /// Only reads the value; the caller keeps ownership.
+    Borrow,
+    /// Takes ownership; the variable cannot be used afterwards.
+    Move,
+}
 
 #[derive(Debug, PartialEq, Clone)]
-enum OwnershipState { Owned, Moved }
+enum OwnershipState {
+    Owned,
+    Moved,
+    /// Declared without a value and not assigned on any path yet.
+    Uninitialized,
+    /// Assigned on some paths only, e.g. in one branch or inside a loop body.
+    MaybeUninitialized,
+    /// A reference-counted `rc<T>` handle, with the number of handles known
+    /// to point at its value: 1 when created, plus one per `.clone()`.
+    Shared(usize),
+    /// Borrowed with `&x`; more shared borrows may follow.
+    BorrowedShared,
+    /// Borrowed with `&mut x`; no other borrow may coexist with it.
+    BorrowedMutable,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Pos { line: usize, column: usize }
@@ -63,19 +89,106 @@ struct VarInfo {
     dtype: String,
     is_constant: bool,
     defined_at: Pos,
+    /// Whether the variable has been read since it was declared.
+    used: bool,
+}
+
+/// Variables of one lexical scope, kept in declaration order so that anything
+/// reported while walking a scope comes out in the same order on every run.
+#[derive(Default)]
+struct Scope {
+    vars: Vec<(String, VarInfo)>,
+    /// Variables borrowed in this scope, with their state before the borrow.
+    /// The borrows end, and the states are restored, when the scope closes.
+    borrows: Vec<(String, OwnershipState)>,
+}
+
+impl Scope {
+    fn get(&self, name: &str) -> Option<&VarInfo> {
+        self.vars.iter().find(|(n, _)| n == name).map(|(_, info)| info)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut VarInfo> {
+        self.vars.iter_mut().find(|(n, _)| n == name).map(|(_, info)| info)
+    }
+
+    fn contains_key(&self, name: &str) -> bool { self.get(name).is_some() }
+
+    fn insert(&mut self, name: String, info: VarInfo) { self.vars.push((name, info)); }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &VarInfo)> {
+        self.vars.iter().map(|(name, info)| (name, info))
+    }
 }
 
 struct BorrowChecker {
-    scopes: Vec<HashMap<String, VarInfo>>,
+    scopes: Vec<Scope>,
     functions: HashMap<String, Pos>,
+    /// Parameter types of every top-level function, collected up front so calls
+    /// can be classified before the callee's declaration is reached.
+    signatures: HashMap<String, Vec<String>>,
+    /// Set while re-analyzing a loop body, whose warnings were already reported on the first pass.
+    quiet: bool,
+    /// Warning codes silenced with `--allow` or `FAX_ALLOW`.
+    allowed: HashSet<String>,
+    /// Lifetime of the reference the enclosing function returns, with the
+    /// lifetime of each of its reference parameters.
+    return_lifetime: Option<(String, HashMap<String, String>)>,
+    /// Field paths such as `obj.x` assigned since they were last read, with where.
+    field_writes: HashMap<String, Pos>,
+    /// Variables whose address was taken; their fields may be read through a pointer.
+    aliased: HashSet<String>,
+    /// Borrows taken by the statement being analyzed, with the states before them.
+    /// Unless a `let` or an assignment binds one, they end with the statement.
+    temporaries: Vec<(String, OwnershipState)>,
 }
 
 impl BorrowChecker {
-    fn new() -> Self { BorrowChecker { scopes: vec![HashMap::new()], functions: HashMap::new() } }
+    fn new() -> Self { BorrowChecker { scopes: vec![Scope::default()], functions: HashMap::new(), signatures: HashMap::new(), quiet: false, allowed: HashSet::new(), return_lifetime: None, field_writes: HashMap::new(), aliased: HashSet::new(), temporaries: Vec::new() } }
     fn is_copy_type(dtype: &str) -> bool { matches!(dtype, "int" | "float" | "bool") }
+    fn is_rc_type(dtype: &str) -> bool { dtype.starts_with("rc<") }
+    fn is_borrowed(state: &OwnershipState) -> bool { matches!(state, OwnershipState::BorrowedShared | OwnershipState::BorrowedMutable) }
 
-    fn enter_scope(&mut self) { self.scopes.push(HashMap::new()); }
-    fn exit_scope(&mut self) { self.scopes.pop(); }
+    /// State of a variable once it holds a value.
+    fn initialized_state(dtype: &str) -> OwnershipState {
+        if BorrowChecker::is_rc_type(dtype) { OwnershipState::Shared(1) } else { OwnershipState::Owned }
+    }
+
+    fn enter_scope(&mut self) { self.scopes.push(Scope::default()); }
+
+    /// Ends `borrows`, restoring each variable to its state before it was borrowed.
+    fn release(&mut self, borrows: Vec<(String, OwnershipState)>) {
+        for (name, before) in borrows.into_iter().rev() {
+            if let Some(info) = self.get_var_mut(&name).filter(|info| BorrowChecker::is_borrowed(&info.state)) {
+                info.state = before;
+            }
+        }
+    }
+
+    /// Ends the borrows of the statement just analyzed that no name holds.
+    fn end_statement(&mut self) {
+        let temporaries = std::mem::take(&mut self.temporaries);
+        self.release(temporaries);
+    }
+
+    /// Keeps the borrows of the current statement until the scope closes, when
+    /// `value` itself is the `&x` or `&mut x` being bound to a name.
+    fn hold_borrow(&mut self, value: &Node) {
+        if !matches!(value, Node::UnaryExpression { operator, .. } if operator == "&" || operator == "&mut") { return; }
+        let temporaries = std::mem::take(&mut self.temporaries);
+        if let Some(scope) = self.scopes.last_mut() { scope.borrows.extend(temporaries); }
+    }
+
+    /// Closes the innermost scope, warning about `rc<T>` values it created but never used.
+    fn exit_scope(&mut self) {
+        let Some(mut scope) = self.scopes.pop() else { return };
+        self.release(std::mem::take(&mut scope.borrows));
+        for (name, info) in scope.iter() {
+            if BorrowChecker::is_rc_type(&info.dtype) && !info.used && matches!(info.state, OwnershipState::Shared(_)) {
+                self.report_warning(name, &info.defined_at, &format!("`{}` is never used", info.dtype), &format!("`{}` is created here and dropped without being used", name), "W0036");
+            }
+        }
+    }
 
     fn get_var_mut(&mut self, name: &str) -> Option<&mut VarInfo> {
         for scope in self.scopes.iter_mut().rev() {
@@ -154,62 +267,347 @@ impl BorrowChecker {
         std::process::exit(1);
     }
 
+    fn report_warning(&self, name: &str, pos: &Pos, msg: &str, label: &str, code: &str) {
+        if self.quiet || self.allowed.contains(code) { return; }
+        let diag = Diagnostic {
+            code: code.to_string(),
+            message: msg.to_string(),
+            primary_span: Span { line: pos.line, column: pos.column, length: name.len(), label: label.to_string() },
+            secondary_spans: vec![], suggestion: None, note: None,
+        };
+        eprintln!("{}", serde_json::to_string(&diag).unwrap());
+    }
+
+    fn snapshot_states(&self) -> Vec<Vec<(String, OwnershipState)>> {
+        self.scopes.iter()
+            .map(|s| s.iter().map(|(k, v)| (k.clone(), v.state.clone())).collect())
+            .collect()
+    }
+
+    /// Whether any variable that was `Owned` in `before` has been moved since.
+    fn moved_since(&self, before: &[Vec<(String, OwnershipState)>]) -> bool {
+        before.iter().zip(&self.scopes).any(|(states, scope)| {
+            states.iter().any(|(name, state)| {
+                matches!(state, OwnershipState::Owned | OwnershipState::Shared(_))
+                    && scope.get(name).is_some_and(|info| info.state == OwnershipState::Moved)
+            })
+        })
+    }
+
+    /// The `obj.a.b` path of a member expression rooted at a variable.
+    fn field_path(node: &Node) -> Option<String> {
+        match node {
+            Node::Identifier { name, .. } => Some(name.clone()),
+            Node::MemberExpression { object, property, .. } => Some(format!("{}.{}", BorrowChecker::field_path(object)?, property)),
+            _ => None,
+        }
+    }
+
+    /// Forgets pending writes to `path`, to fields inside it, and to fields containing it.
+    fn read_field(&mut self, path: &str) {
+        self.field_writes.retain(|written, _| {
+            !(written == path || written.starts_with(&format!("{}.", path)) || path.starts_with(&format!("{}.", written)))
+        });
+    }
+
+    /// Records a write to `path`, warning if the value of the previous write was never read.
+    /// Fields of pointers, references, `rc<T>` values and borrowed variables are skipped,
+    /// since another alias may read them in between.
+    fn write_field(&mut self, path: String, position: &Option<Pos>) {
+        let root = path.split('.').next().unwrap_or_default();
+        let Some(info) = self.get_var(root) else { return };
+        if self.aliased.contains(root) || ["ptr<", "ref<", "rc<"].iter().any(|p| info.dtype.starts_with(p)) { return; }
+        let pos = position.clone().unwrap_or(info.defined_at.clone());
+        self.field_writes.retain(|written, _| !written.starts_with(&format!("{}.", path)));
+        if let Some(previous) = self.field_writes.insert(path.clone(), pos.clone()) {
+            let label = format!("overwritten on line {} before it is read", pos.line);
+            self.report_warning(&path, &previous, &format!("value assigned to `{}` is never read", path), &label, "W0012");
+        }
+    }
+
+    /// For `name = name + 0` and the like, as `name += 0` is parsed, describes
+    /// the operation that leaves `name` unchanged.
+    fn identity_update(name: &str, value: &Node) -> Option<&'static str> {
+        let Node::BinaryExpression { operator, left, right, .. } = value else { return None };
+        if !matches!(&**left, Node::Identifier { name: lhs, .. } if lhs == name) { return None; }
+        let Node::Literal { value, .. } = &**right else { return None };
+        match (operator.as_str(), value.as_f64()?) {
+            ("+", 0.0) => Some("adding `0`"),
+            ("-", 0.0) => Some("subtracting `0`"),
+            ("*", 1.0) => Some("multiplying by `1`"),
+            ("/", 1.0) => Some("dividing by `1`"),
+            _ => None,
+        }
+    }
+
+    /// Analyzes one loop iteration. If it moved a variable declared outside the
+    /// loop, runs it once more so a use at the top of the next iteration is caught.
+    fn analyze_loop(&mut self, iteration: &[&Node]) {
+        // The body may run any number of times, so no write is known dead across the loop boundary.
+        self.field_writes.clear();
+        let before = self.snapshot_states();
+        for part in iteration { self.analyze(part); }
+        if self.moved_since(&before) {
+            let quiet = std::mem::replace(&mut self.quiet, true);
+            for part in iteration { self.analyze(part); }
+            self.quiet = quiet;
+        }
+        self.field_writes.clear();
+        // The body might run zero times, so anything it initialized may still be uninitialized.
+        for (states, scope) in before.iter().zip(self.scopes.iter_mut()) {
+            for (name, state) in states {
+                if matches!(state, OwnershipState::Uninitialized | OwnershipState::MaybeUninitialized) {
+                    if let Some(info) = scope.get_mut(name) {
+                        if matches!(info.state, OwnershipState::Owned | OwnershipState::Shared(_)) { info.state = OwnershipState::MaybeUninitialized; }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records `&name` or `&mut name`, rejecting a mutable borrow that would
+    /// overlap another borrow, or any borrow of a value borrowed mutably.
+    fn borrow(&mut self, name: &str, mutable: bool, position: &Option<Pos>) {
+        self.aliased.insert(name.to_string());
+        self.read_field(name);
+        self.check_initialized(name, position);
+        let Some(info) = self.get_var(name) else { return };
+        let pos = position.clone().unwrap_or(info.defined_at.clone());
+        match (&info.state, mutable) {
+            (OwnershipState::BorrowedMutable, false) => self.report_error(name, &pos, &format!("cannot borrow `{}` as immutable because it is also borrowed as mutable", name), "immutable borrow occurs here", "E0502"),
+            (OwnershipState::BorrowedMutable, true) => self.report_error(name, &pos, &format!("cannot borrow `{}` as mutable more than once at a time", name), "second mutable borrow occurs here", "E0499"),
+            (OwnershipState::BorrowedShared, true) => self.report_error(name, &pos, &format!("cannot borrow `{}` as mutable because it is also borrowed as immutable", name), "mutable borrow occurs here", "E0502"),
+            (OwnershipState::Moved, _) => self.report_error(name, &pos, &format!("borrow of moved value: `{}`", name), "value borrowed here after move", "E0382"),
+            _ => {}
+        }
+        let Some(info) = self.get_var_mut(name) else { return };
+        info.used = true;
+        let before = std::mem::replace(&mut info.state, if mutable { OwnershipState::BorrowedMutable } else { OwnershipState::BorrowedShared });
+        if !BorrowChecker::is_borrowed(&before) {
+            self.temporaries.push((name.to_string(), before));
+        }
+    }
+
+    /// Classifies argument `index` of a call: `println` and `ref<T>` parameters only
+    /// borrow it, everything else takes ownership.
+    fn argument_use(&self, callee: &Node, index: usize) -> UseKind {
+        let Node::Identifier { name, .. } = callee else { return UseKind::Move };
+        if name == "println" { return UseKind::Borrow; }
+        match self.signatures.get(name).and_then(|params| params.get(index)) {
+            Some(param_type) if param_type.starts_with("ref<") => UseKind::Borrow,
+            _ => UseKind::Move,
+        }
+    }
+
+    /// Rejects returning a reference parameter whose lifetime is not the one the signature declares.
+    fn check_return_lifetime(&self, arg: &Node, position: &Option<Pos>) {
+        let (Node::Identifier { name, position: at }, Some((expected, inputs))) = (arg, &self.return_lifetime) else { return };
+        let Some(found) = inputs.get(name).filter(|found| *found != expected) else { return };
+        let pos = at.clone().or(position.clone()).unwrap_or(Pos { line: 0, column: 0 });
+        self.report_error(name, &pos, "lifetime mismatch",
+            &format!("returns a reference with lifetime `'{}`, but the signature promises `'{}`", found, expected), "E0623");
+    }
+
+    fn check_initialized(&self, name: &str, position: &Option<Pos>) {
+        let Some(info) = self.get_var(name) else { return };
+        let pos = position.clone().unwrap_or(info.defined_at.clone());
+        match info.state {
+            OwnershipState::Uninitialized => self.report_error(name, &pos, &format!("used binding `{}` isn't initialized", name), &format!("`{}` used here but it isn't initialized", name), "E0381"),
+            OwnershipState::MaybeUninitialized => self.report_error(name, &pos, &format!("used binding `{}` is possibly-uninitialized", name), &format!("`{}` used here but it is possibly-uninitialized", name), "E0381"),
+            _ => {}
+        }
+    }
+
+    /// Whether every path through `node` ends in a `return`.
+    fn always_returns(node: &Node) -> bool {
+        match node {
+            Node::ReturnStatement { .. } => true,
+            Node::BlockStatement { body, .. } => body.iter().any(BorrowChecker::always_returns),
+            Node::IfStatement { consequent, alternate: Some(alternate), .. } => {
+                BorrowChecker::always_returns(consequent) && BorrowChecker::always_returns(alternate)
+            }
+            _ => false,
+        }
+    }
+
+    fn restore_states(&mut self, states: &[Vec<(String, OwnershipState)>]) {
+        for (i, scope_states) in states.iter().enumerate() {
+            for (name, state) in scope_states {
+                if let Some(info) = self.scopes[i].get_mut(name) {
+                    info.state = state.clone();
+                }
+            }
+        }
+    }
+
+    /// Analyzes two exclusive branches from the same starting state. A value moved
+    /// in either branch is considered moved afterwards, unless that branch returns:
+    /// the code after the `if` then only runs after the other branch.
+    fn analyze_branches(&mut self, consequent: &Node, alternate: Option<&Node>) {
+        // Capture states before branching
+        let before_states = self.snapshot_states();
+
+        let writes_before = self.field_writes.clone();
+
+        self.analyze(consequent);
+
+        // Capture states after consequent
+        let after_consequent = self.snapshot_states();
+        let writes_after_consequent = std::mem::replace(&mut self.field_writes, writes_before);
+
+        // Reset to before state for alternate
+        self.restore_states(&before_states);
+
+        if let Some(alt) = alternate {
+            self.analyze(alt);
+        }
+        // A write is only known to be unread if it is pending after both branches.
+        self.field_writes.retain(|path, _| writes_after_consequent.contains_key(path));
+
+        if BorrowChecker::always_returns(consequent) { return; }
+        if alternate.is_some_and(BorrowChecker::always_returns) {
+            self.restore_states(&after_consequent);
+            return;
+        }
+
+        // Merge states: if moved in EITHER branch, it's moved; if only one branch
+        // initialized it, it may still be uninitialized
+        for (i, scope_states) in after_consequent.iter().enumerate() {
+            for (name, state) in scope_states {
+                if let Some(info) = self.scopes[i].get_mut(name) {
+                    match (state, &info.state) {
+                        (OwnershipState::Moved, _) => info.state = OwnershipState::Moved,
+                        (OwnershipState::Shared(a), OwnershipState::Shared(b)) => info.state = OwnershipState::Shared(*a.max(b)),
+                        (OwnershipState::Owned, OwnershipState::Owned) | (_, OwnershipState::Moved) => {}
+                        // A borrow taken in either branch is live after both; `release` ends it.
+                        (OwnershipState::BorrowedMutable, _) | (_, OwnershipState::BorrowedMutable) => info.state = OwnershipState::BorrowedMutable,
+                        (OwnershipState::BorrowedShared, _) | (_, OwnershipState::BorrowedShared) => info.state = OwnershipState::BorrowedShared,
+                        (OwnershipState::Uninitialized, OwnershipState::Uninitialized) => {}
+                        (a, b) if a == b => {}
+                        _ => info.state = OwnershipState::MaybeUninitialized,
+                    }
+                }
+            }
+        }
+    }
+
     fn analyze(&mut self, node: &Node) {
         match node {
-            Node::Program { body } => { for stmt in body { self.analyze(stmt); } }
+            Node::Program { body } => {
+                for stmt in body {
+                    if let Node::FunctionDeclaration { name, params, .. } = stmt {
+                        self.signatures.insert(name.clone(), params.iter().map(|p| p.param_type.clone()).collect());
+                    }
+                }
+                for stmt in body { self.analyze(stmt); }
+            }
             Node::VariableDeclaration { identifier, dataType, isConstant, initializer, position, .. } => {
-                if let Some(init) = initializer { self.analyze(init); }
+                if let Some(init) = initializer {
+                    self.analyze(init);
+                    self.hold_borrow(init);
+                    self.end_statement();
+                }
+                // `let b = a;` moves an `rc<T>` handle; sharing it takes `a.clone()`.
+                if let Some(Node::Identifier { name, .. }) = initializer.as_deref() {
+                    if let Some(info) = self.get_var_mut(name).filter(|info| BorrowChecker::is_rc_type(&info.dtype)) {
+                        info.state = OwnershipState::Moved;
+                    }
+                }
                 let pos = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+                self.read_field(identifier);
                 self.define_var(identifier.clone(), VarInfo {
-                    state: OwnershipState::Owned,
+                    state: if initializer.is_some() { BorrowChecker::initialized_state(dataType) } else { OwnershipState::Uninitialized },
                     dtype: dataType.clone(),
                     is_constant: isConstant.unwrap_or(false),
                     defined_at: pos,
+                    used: false,
                 });
             }
             Node::AssignmentExpression { left, right, position } => {
                 self.analyze(right);
+                self.hold_borrow(right);
                 if let Node::Identifier { name, .. } = &**left {
                     if let Some(info) = self.get_var(name) {
                         if info.is_constant {
                             let pos = position.clone().unwrap_or(info.defined_at.clone());
                             self.report_error(name, &pos, &format!("cannot assign to constant variable `{}`", name), "re-assignment of constant", "E0384");
                         }
+                        if matches!(&**right, Node::Identifier { name: rhs, .. } if rhs == name) {
+                            let pos = position.clone().unwrap_or(info.defined_at.clone());
+                            if BorrowChecker::is_copy_type(&info.dtype) {
+                                self.report_warning(name, &pos, &format!("variable `{}` is assigned to itself", name), "this assignment has no effect", "W0017");
+                            } else {
+                                self.report_warning(name, &pos, "useless self-assignment of moved value", &format!("`{}` is moved into itself", name), "W0023");
+                            }
+                        }
+                        if let Some(effect) = BorrowChecker::identity_update(name, right) {
+                            let pos = position.clone().unwrap_or(info.defined_at.clone());
+                            self.report_warning(name, &pos, "assignment has no effect", &format!("{} does not change `{}`", effect, name), "W0017");
+                        }
                     }
+                    // Assigning (re-)initializes the variable, whatever state it was in.
+                    if let Some(info) = self.get_var_mut(name) { info.state = BorrowChecker::initialized_state(&info.dtype); }
+                    self.read_field(name);
+                } else if let Some(path) = BorrowChecker::field_path(left) {
+                    self.write_field(path, position);
+                } else {
+                    self.analyze(left);
                 }
-                self.analyze(left);
             }
             Node::Identifier { name, position } => {
+                self.check_initialized(name, position);
                 if let Some(info) = self.get_var(name) {
                     if info.state == OwnershipState::Moved {
                         let pos = position.clone().unwrap_or(info.defined_at.clone());
                         self.report_error(name, &pos, &format!("use of moved value: `{}`", name), "value used here after move", "E0382");
                     }
                 }
+                if let Some(info) = self.get_var_mut(name) { info.used = true; }
+                self.read_field(name);
             }
+            Node::MemberExpression { object, .. } => match BorrowChecker::field_path(node) {
+                Some(path) => self.read_field(&path),
+                None => self.analyze(object),
+            },
             Node::WhileStatement { test, body, .. } => {
-                self.analyze(test);
-                self.analyze(body);
+                self.analyze_loop(&[test, body]);
             }
             Node::ForStatement { init, test, update, body, .. } => {
                 self.enter_scope();
-                if let Some(ref i) = init { self.analyze(&*i); }
-                if let Some(ref t) = test { self.analyze(&*t); }
-                if let Some(ref u) = update { self.analyze(&*u); }
-                self.analyze(body);
+                if let Some(i) = init { self.analyze(i); }
+                let iteration: Vec<&Node> = test.iter().chain([body]).chain(update.iter()).map(|n| &**n).collect();
+                self.analyze_loop(&iteration);
                 self.exit_scope();
             }
             Node::CallExpression { callee, arguments, .. } => {
-                let is_println = if let Node::Identifier { name, .. } = &**callee { name == "println" } else { false };
-                for arg in arguments {
+                // `a.clone()` on an `rc<T>` hands out another handle to the same value.
+                if let Node::MemberExpression { object, property, .. } = &**callee {
+                    self.analyze(object);
+                    if let Node::Identifier { name, .. } = &**object {
+                        if let Some(info) = self.get_var_mut(name).filter(|_| property == "clone") {
+                            if let OwnershipState::Shared(count) = info.state { info.state = OwnershipState::Shared(count + 1); }
+                        }
+                    }
+                }
+                for (i, arg) in arguments.iter().enumerate() {
                     if let Node::Identifier { name, position } = arg {
+                        self.check_initialized(name, position);
+                        let use_kind = self.argument_use(callee, i);
+                        self.read_field(name);
                         if let Some(info) = self.get_var_mut(name) {
+                            info.used = true;
                             if !BorrowChecker::is_copy_type(&info.dtype) {
                                 if info.state == OwnershipState::Moved {
                                     let pos = position.clone().unwrap_or(info.defined_at.clone());
-                                    self.report_error(name, &pos, &format!("cannot move already moved value `{}`", name), "attempt to move again", "E0382");
+                                    let (msg, label) = match use_kind {
+                                        // Only the re-run of a loop body can see its own earlier move.
+                                        UseKind::Move if self.quiet => ("value moved in previous iteration of loop".to_string(), "value moved here, in previous iteration of loop"),
+                                        UseKind::Move => (format!("cannot move already moved value `{}`", name), "attempt to move again"),
+                                        UseKind::Borrow => (format!("borrow of moved value: `{}`", name), "value borrowed here after move"),
+                                    };
+                                    self.report_error(name, &pos, &msg, label, "E0382");
                                 }
-                                if !is_println {
+                                if use_kind == UseKind::Move {
                                     info.state = OwnershipState::Moved;
                                 }
                             }
@@ -217,12 +615,28 @@ impl BorrowChecker {
                     } else { self.analyze(arg); }
                 }
             }
-            Node::FunctionDeclaration { name, body, position, .. } => {
+            Node::FunctionDeclaration { name, params, returnLifetime, body, position, .. } => {
                 let pos = position.clone().unwrap_or(Pos { line: 0, column: 0 });
-                self.define_fn(name.clone(), pos);
+                self.define_fn(name.clone(), pos.clone());
+                let enclosing = self.return_lifetime.take();
+                if let Some(lifetime) = returnLifetime {
+                    let inputs: HashMap<String, String> = params.iter()
+                        .filter_map(|p| Some((p.name.clone(), p.lifetime.clone()?)))
+                        .collect();
+                    if !inputs.values().any(|l| l == lifetime) {
+                        let tick = format!("'{}", lifetime);
+                        self.report_error(&tick, &pos, &format!("use of undeclared lifetime name `{}`", tick), "the returned reference must borrow from a parameter with this lifetime", "E0261");
+                    }
+                    self.return_lifetime = Some((lifetime.clone(), inputs));
+                }
+                // A nested function's scope sits on top of the enclosing ones, so
+                // outer bindings that were already moved are reported inside it too.
+                let outer_writes = std::mem::take(&mut self.field_writes);
                 self.enter_scope();
                 self.analyze(body);
                 self.exit_scope();
+                self.field_writes = outer_writes;
+                self.return_lifetime = enclosing;
             }
             Node::BlockStatement { body, .. } => { 
                 self.enter_scope();
@@ -231,58 +645,63 @@ impl BorrowChecker {
             }
             Node::IfStatement { test, consequent, alternate, .. } => {
                 self.analyze(test);
-                
-                // Capture states before branching
-                let before_states: Vec<HashMap<String, OwnershipState>> = self.scopes.iter()
-                    .map(|s| s.iter().map(|(k, v)| (k.clone(), v.state.clone())).collect())
-                    .collect();
-
-                self.analyze(consequent);
-                
-                // Capture states after consequent
-                let after_consequent: Vec<HashMap<String, OwnershipState>> = self.scopes.iter()
-                    .map(|s| s.iter().map(|(k, v)| (k.clone(), v.state.clone())).collect())
-                    .collect();
-
-                // Reset to before state for alternate
-                for (i, scope_states) in before_states.iter().enumerate() {
-                    for (name, state) in scope_states {
-                        if let Some(info) = self.scopes[i].get_mut(name) {
-                            info.state = state.clone();
-                        }
-                    }
-                }
-
-                if let Some(alt) = alternate {
-                    self.analyze(alt);
-                }
-
-                // Merge states: if moved in EITHER branch, it's moved
-                for (i, scope_states) in after_consequent.iter().enumerate() {
-                    for (name, state) in scope_states {
-                        if *state == OwnershipState::Moved {
-                            if let Some(info) = self.scopes[i].get_mut(name) {
-                                info.state = OwnershipState::Moved;
-                            }
-                        }
-                    }
-                }
+                self.end_statement();
+                self.analyze_branches(consequent, alternate.as_deref());
             }
-            Node::ExpressionStatement { expression } => self.analyze(expression),
-            Node::ReturnStatement { argument, .. } => {
-                if let Some(ref arg) = argument { self.analyze(&*arg); }
+            Node::ConditionalExpression { test, consequent, alternate, .. } => {
+                self.analyze(test);
+                self.analyze_branches(consequent, Some(alternate));
             }
+            Node::UnaryExpression { operator, argument } => match (operator.as_str(), &**argument) {
+                ("&" | "&mut", Node::Identifier { name, position }) => self.borrow(name, operator == "&mut", position),
+                _ => self.analyze(argument),
+            },
+            Node::ExpressionStatement { expression } => {
+                self.analyze(expression);
+                self.end_statement();
+            }
+            Node::ReturnStatement { argument: Some(arg), position } => {
+                self.check_return_lifetime(arg, position);
+                self.analyze(arg);
+                self.end_statement();
+                self.field_writes.clear();
+            }
+            Node::BreakStatement { argument, .. } => {
+                if let Some(arg) = argument { self.analyze(arg); }
+                // Writes pending here may be read wherever control goes next.
+                self.field_writes.clear();
+            }
+            Node::ReturnStatement { .. } | Node::ContinueStatement { .. } => self.field_writes.clear(),
+            Node::BinaryExpression { left, right, .. } => {
+                self.analyze(left);
+                self.analyze(right);
+            }
+            // Nodes this pass does not model may read any field.
+            Node::Unknown => self.field_writes.clear(),
             _ => {}
         }
     }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 { return; }
-    let input = fs::read_to_string(&args[1]).expect("Failed to read AST");
+    let mut args = env::args().skip(1);
+    let mut path = None;
+    let mut allowed: HashSet<String> = env::var("FAX_ALLOW").unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|code| !code.is_empty())
+        .map(str::to_uppercase)
+        .collect();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow" => allowed.extend(args.next().map(|code| code.to_uppercase())),
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else { return };
+    let input = fs::read_to_string(&path).expect("Failed to read AST");
     let ast: Node = serde_json::from_str(&input).expect("Failed to parse AST JSON");
     let mut checker = BorrowChecker::new();
+    checker.allowed = allowed;
     checker.analyze(&ast);
     println!("{}", input);
 }
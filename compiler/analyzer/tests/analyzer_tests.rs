@@ -0,0 +1,263 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn run_analyzer(fixture: &str) -> Output {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture].iter().collect();
+    Command::new(env!("CARGO_BIN_EXE_fax-analyzer"))
+        .arg(path)
+        .output()
+        .expect("failed to run fax-analyzer")
+}
+
+fn diagnostics(output: &Output) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("diagnostic is not valid JSON"))
+        .collect()
+}
+
+#[test]
+fn test_self_move_of_string_warns() {
+    let output = run_analyzer("self_assign_string.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0023");
+    assert_eq!(diags[0]["message"], "useless self-assignment of moved value");
+}
+
+#[test]
+fn test_self_assignment_of_int_is_plain_warning() {
+    let output = run_analyzer("self_assign_int.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0017");
+}
+
+#[test]
+fn test_assignment_from_other_variable_does_not_warn() {
+    let output = run_analyzer("assign_other_variable.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_identity_compound_assignment_warns() {
+    let output = run_analyzer("compound_assign_identity.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0017");
+    assert_eq!(diags[0]["message"], "assignment has no effect");
+}
+
+#[test]
+fn test_allowed_warning_is_not_emitted() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "self_assign_int.json"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-analyzer"))
+        .args(["--allow", "W0012", "--allow", "W0017"])
+        .arg(&path)
+        .output()
+        .expect("failed to run fax-analyzer");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-analyzer"))
+        .env("FAX_ALLOW", "w0017")
+        .arg(&path)
+        .output()
+        .expect("failed to run fax-analyzer");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_move_caught_on_next_loop_iteration() {
+    let output = run_analyzer("move_in_loop.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0382");
+    assert_eq!(diags[0]["primary_span"]["line"], 3);
+}
+
+#[test]
+fn test_move_in_conditional_arm_is_possible_move() {
+    let output = run_analyzer("move_in_conditional.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0382");
+    assert_eq!(diags[0]["primary_span"]["line"], 3);
+}
+
+#[test]
+fn test_read_after_loop_only_initialization() {
+    let output = run_analyzer("init_inside_loop.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0381");
+    assert_eq!(diags[0]["message"], "used binding `count` is possibly-uninitialized");
+    assert_eq!(diags[0]["primary_span"]["line"], 5);
+}
+
+#[test]
+fn test_initialized_before_loop_stays_initialized() {
+    let output = run_analyzer("init_before_loop.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_borrowing_in_loop_body_is_allowed() {
+    let output = run_analyzer("borrow_in_loop.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_moving_in_loop_body_is_error() {
+    let output = run_analyzer("move_each_iteration.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0382");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+}
+
+#[test]
+fn test_move_in_while_reports_previous_iteration() {
+    let output = run_analyzer("move_string_in_while.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0382");
+    assert_eq!(diags[0]["message"], "value moved in previous iteration of loop");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+    assert_eq!(diags[0]["primary_span"]["column"], 13);
+}
+
+#[test]
+fn test_moved_value_used_in_nested_function() {
+    let output = run_analyzer("moved_in_nested_fn.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0382");
+    assert_eq!(diags[0]["message"], "borrow of moved value: `s`");
+    assert_eq!(diags[0]["primary_span"]["line"], 5);
+}
+
+#[test]
+fn test_rc_shared_through_clone() {
+    let output = run_analyzer("rc_shared_by_clone.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_unused_rc_warns() {
+    let output = run_analyzer("rc_unused.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0036");
+    assert_eq!(diags[0]["message"], "`rc<string>` is never used");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+}
+
+#[test]
+fn test_scope_diagnostics_follow_declaration_order() {
+    for _ in 0..8 {
+        let output = run_analyzer("rc_unused_many.json");
+        assert!(output.status.success());
+        let lines: Vec<_> = diagnostics(&output).iter().map(|d| d["primary_span"]["line"].as_u64().unwrap()).collect();
+        assert_eq!(lines, [2, 3, 4, 5, 6, 7]);
+    }
+}
+
+#[test]
+fn test_return_with_declared_lifetime() {
+    let output = run_analyzer("lifetime_return_declared.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_return_of_other_input_lifetime_is_rejected() {
+    let output = run_analyzer("lifetime_return_other_input.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0623");
+    assert_eq!(diags[0]["primary_span"]["label"], "returns a reference with lifetime `'y`, but the signature promises `'x`");
+    assert_eq!(diags[0]["primary_span"]["column"], 12);
+}
+
+#[test]
+fn test_move_followed_by_return_in_loop_is_accepted() {
+    let output = run_analyzer("move_then_return_in_loop.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_two_shared_borrows_coexist() {
+    let output = run_analyzer("borrow_shared_twice.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_mutable_borrow_while_shared_is_rejected() {
+    let output = run_analyzer("borrow_shared_then_mutable.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0502");
+    assert_eq!(diags[0]["primary_span"]["line"], 3);
+}
+
+#[test]
+fn test_borrow_ends_with_its_block() {
+    let output = run_analyzer("borrow_released_at_block_end.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_borrowed_argument_ends_with_its_statement() {
+    let output = run_analyzer("borrow_argument_released.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_borrow_in_one_conditional_arm_keeps_binding_initialized() {
+    let output = run_analyzer("borrow_in_conditional.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_overwritten_field_store_warns() {
+    let output = run_analyzer("dead_field_store.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0012");
+    assert_eq!(diags[0]["message"], "value assigned to `p.x` is never read");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+}
+
+#[test]
+fn test_field_store_read_before_overwrite_is_accepted() {
+    let output = run_analyzer("field_store_read_before_overwrite.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_field_store_of_aliased_variable_is_not_flagged() {
+    let output = run_analyzer("field_store_through_alias.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
@@ -0,0 +1,132 @@
+//! Dependency graph for `--emit deps`: which functions call which, which
+//! functions refer to which structs, and which structs hold which structs.
+
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+#[derive(Default)]
+struct Edges {
+    calls: BTreeSet<String>,
+    uses: BTreeSet<String>,
+}
+
+/// Names in a type string such as `array<Point, 4>` or `ref<Line>` that are declared structs.
+fn struct_names(type_name: &str, structs: &BTreeSet<String>, out: &mut BTreeSet<String>) {
+    for part in type_name.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        if structs.contains(part) { out.insert(part.to_string()); }
+    }
+}
+
+/// Collects the calls and struct references anywhere inside `node`.
+fn collect(node: &Value, functions: &BTreeSet<String>, structs: &BTreeSet<String>, edges: &mut Edges) {
+    match node {
+        Value::Object(obj) => {
+            match obj.get("type").and_then(Value::as_str) {
+                Some("CallExpression") => {
+                    if let Some(callee) = obj["callee"].get("name").and_then(Value::as_str) {
+                        if functions.contains(callee) { edges.calls.insert(callee.to_string()); }
+                    }
+                }
+                Some("StructLiteral") => {
+                    if let Some(name) = obj["name"].as_str() { struct_names(name, structs, &mut edges.uses); }
+                }
+                _ => {}
+            }
+            // Type annotations of variables, parameters, return values and casts.
+            for key in ["dataType", "returnType", "targetType"] {
+                if let Some(t) = obj.get(key).and_then(Value::as_str) { struct_names(t, structs, &mut edges.uses); }
+            }
+            if let Some(params) = obj.get("params").and_then(Value::as_array) {
+                for p in params {
+                    if let Some(t) = p["type"].as_str() { struct_names(t, structs, &mut edges.uses); }
+                }
+            }
+            for value in obj.values() { collect(value, functions, structs, edges); }
+        }
+        Value::Array(items) => {
+            for item in items { collect(item, functions, structs, edges); }
+        }
+        _ => {}
+    }
+}
+
+/// A call to the function `callee` anywhere inside `node`.
+pub fn find_call<'a>(node: &'a Value, callee: &str) -> Option<&'a Value> {
+    match node {
+        Value::Object(obj) if obj.get("type").and_then(Value::as_str) == Some("CallExpression")
+            && obj["callee"].get("name").and_then(Value::as_str) == Some(callee) => Some(node),
+        Value::Object(obj) => obj.values().find_map(|value| find_call(value, callee)),
+        Value::Array(items) => items.iter().find_map(|item| find_call(item, callee)),
+        _ => None,
+    }
+}
+
+fn sorted(set: BTreeSet<String>) -> Value {
+    Value::Array(set.into_iter().map(Value::String).collect())
+}
+
+/// Builds the adjacency list of a `Program`, keyed by symbol name. Methods are
+/// keyed as `Type.method`.
+pub fn dependency_graph(program: &Value) -> Value {
+    let body = program["body"].as_array().cloned().unwrap_or_default();
+    let name_of = |stmt: &Value, kind: &str| (stmt["type"] == kind).then(|| stmt["name"].as_str().unwrap_or_default().to_string());
+    let functions: BTreeSet<String> = body.iter().filter_map(|s| name_of(s, "FunctionDeclaration")).collect();
+    let structs: BTreeSet<String> = body.iter().filter_map(|s| name_of(s, "StructDeclaration")).collect();
+
+    let mut graph = BTreeMap::new();
+    let mut add_function = |key: String, decl: &Value| {
+        let mut edges = Edges::default();
+        collect(decl, &functions, &structs, &mut edges);
+        graph.insert(key, json!({ "kind": "function", "calls": sorted(edges.calls), "uses": sorted(edges.uses) }));
+    };
+    for stmt in &body {
+        match stmt["type"].as_str() {
+            Some("FunctionDeclaration") => add_function(stmt["name"].as_str().unwrap_or_default().to_string(), stmt),
+            Some("ImplBlock") => {
+                let type_name = stmt["typeName"].as_str().unwrap_or_default();
+                for method in stmt["methods"].as_array().into_iter().flatten() {
+                    add_function(format!("{}.{}", type_name, method["name"].as_str().unwrap_or_default()), method);
+                }
+            }
+            _ => {}
+        }
+    }
+    for stmt in body.iter().filter(|s| s["type"] == "StructDeclaration") {
+        let mut contains = BTreeSet::new();
+        for field in stmt["fields"].as_array().into_iter().flatten() {
+            if let Some(t) = field["type"].as_str() { struct_names(t, &structs, &mut contains); }
+        }
+        graph.insert(stmt["name"].as_str().unwrap_or_default().to_string(), json!({ "kind": "struct", "contains": sorted(contains) }));
+    }
+    Value::Object(graph.into_iter().collect::<Map<String, Value>>())
+}
+
+/// Top-level functions that cannot be reached through the call graph from an
+/// entry point: `main`, any method, since calls to methods are not tracked, or
+/// a function called by a top-level statement or global initializer, which run
+/// before `main`. Empty for a program without `main`, which has no known entry point.
+pub fn unreachable_functions(program: &Value) -> BTreeSet<String> {
+    let graph = dependency_graph(program);
+    let Some(graph) = graph.as_object().filter(|g| g.contains_key("main")) else { return BTreeSet::new() };
+    let functions: BTreeSet<String> = graph.iter().filter(|(_, node)| node["kind"] == "function").map(|(name, _)| name.clone()).collect();
+    let mut top_level = Edges::default();
+    for stmt in program["body"].as_array().into_iter().flatten() {
+        if !matches!(stmt["type"].as_str(), Some("FunctionDeclaration" | "ImplBlock" | "StructDeclaration" | "EnumDeclaration")) {
+            collect(stmt, &functions, &BTreeSet::new(), &mut top_level);
+        }
+    }
+    let mut queue: VecDeque<&str> = graph.keys()
+        .filter(|k| *k == "main" || k.contains('.') || top_level.calls.contains(*k))
+        .map(String::as_str)
+        .collect();
+    let mut reached: BTreeSet<&str> = queue.iter().copied().collect();
+    while let Some(name) = queue.pop_front() {
+        for callee in graph[name]["calls"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+            if reached.insert(callee) { queue.push_back(callee); }
+        }
+    }
+    graph.iter()
+        .filter(|(name, node)| node["kind"] == "function" && !reached.contains(name.as_str()))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
@@ -0,0 +1,293 @@
+//! Extended documentation for diagnostic codes, printed by `--explain CODE`.
+//!
+//! Covers the codes emitted by the checker and the analyzer so either stage's
+//! output can be looked up from one place.
+
+const EXPLANATIONS: &[(&str, &str)] = &[
+    ("E0004", "A `match` does not cover every possible value.
+
+A `match` on an enum needs an arm for each variant, and a `match` on a `bool`
+needs arms for both `true` and `false`. Values of other types can only be
+covered completely with a `_` arm. Add the missing arms, or a `_` arm that
+handles the rest."),
+    ("E0061", "A function was called with the wrong number of arguments.
+
+Every parameter in the function's signature needs exactly one argument.
+Check the declaration and add or remove arguments to match it. The same holds
+for a positional struct literal such as `Point { 1, 2 }`, which needs one value
+per field."),
+    ("E0062", "A struct literal sets the same field twice.
+
+Each field of a struct literal may be given only once. Remove the duplicate."),
+    ("E0063", "A struct literal does not initialize every field of the struct.
+
+Fax-lang has no implicit default values, so each field must be given a value,
+including the fields of struct literals nested inside it. The label names the
+missing field by its path, e.g. `inner.x`."),
+    ("E0069", "A bare `return;` appears in a function that returns a value.
+
+A function declared with a return type other than `void` must give a value
+to every `return`: write `return value;` with a value of the declared type."),
+    ("E0080", "A constant evaluated to a value that is not allowed where it is used.
+
+Fixed-size array types such as `array<int, N>` need `N` to be a positive
+integer. The arms of a `match` on an `int` are evaluated as constants, and two
+arms may not match the same value, as in `1 => ...` followed by `0 + 1 => ...`.
+An enum discriminant such as `Read = 1` must be a constant integer, and each
+variant of a `@bitflags` enum needs one that is a single power of two."),
+    ("E0081", "Two variants of an enum were given the same discriminant.
+
+Each explicit discriminant, as in `Read = 1`, must be unique within its enum.
+Change one of the values, or remove the variant that duplicates the other."),
+    ("E0107", "A struct type was written with the wrong number of type arguments.
+
+A generic struct such as `struct Box<T> { value: T }` is used as `Box<int>`,
+with one argument for each type parameter; its fields then have the argument
+in place of `T`. A struct without type parameters takes no arguments."),
+    ("E0116", "An `impl` block names a type that is not declared in this module.
+
+Methods can only be added to structs declared in the same module. Check the
+type name, or declare the struct before adding methods to it."),
+    ("E0128", "A name is defined twice in the same scope.
+
+Variables cannot be re-declared in the block that already declares them, and a
+variable cannot share its name with a function. Rename one of them, or drop
+`let` if you meant to assign to the existing variable."),
+    ("E0201", "A method is defined more than once for the same type.
+
+Method names must be unique per type, across the struct body and all of its
+`impl` blocks. Rename or remove one of the definitions."),
+    ("E0261", "A returned reference names a lifetime that no parameter has.
+
+In `fn f(a: &'x T) -> &'y T` the result must borrow from one of the inputs, so
+its lifetime has to be one of the parameters' lifetimes, here `'x`."),
+    ("E0277", "A value of this type cannot be used here.
+
+`println` only accepts `int`, `float`, `bool` and `string` values. Print the
+individual fields of a struct instead of the struct itself. Arrays and
+pointers can only be indexed with an `int`, so `a[\"0\"]` is rejected as well."),
+    ("E0282", "The type of a variable cannot be inferred.
+
+`let x = value;` gives `x` the type of `value`, but `let x;` has nothing to
+infer it from. Annotate the declaration, e.g. `let x: int;`, or give it a value."),
+    ("E0308", "Mismatched types.
+
+A value of one type was used where another type was expected: as a variable
+initializer, an argument, a return or tail value, an operand, or a value that
+leaves a labeled block. Fax-lang does not convert types implicitly; use an
+`as` cast for numeric conversions. A call to a `void` function has no value
+and can only be used as a statement. The condition of an `if`, `while` or
+`for` must be a `bool`. Every `return` in a function must agree
+with its return type; with `-> auto`, they must agree with each other.
+
+A `T` or `null` can be stored in an `optional<T>` (also written `T?`), but an
+`optional<T>` is only usable as a `T` after checking it, with
+`if (x != null)` or `if let`. The producer of an `if let` or `while let` must
+be an `optional<T>`.
+
+The length of a fixed-size array is part of its type: an `array<int, 3>` only
+accepts array literals and arrays of exactly three elements. Arrays declared
+without a length, such as `array<int>`, accept any length.
+
+A `match` arm such as `Reading.Count(n: int)` binds the payload of an enum
+variant; the type written for the binding must be the payload type declared
+on the variant. Leave the type out, `Reading.Count(n)`, to take it from the
+variant."),
+    ("E0317", "A `const` is declared without a value.
+
+A constant can never be assigned after its declaration, so it must be given
+its value where it is declared: `const LIMIT: int = 10;`. Use `let` for a
+variable that is assigned later."),
+    ("E0369", "A bitwise operator was applied to an enum that does not support it.
+
+Only values of an enum declared with `@bitflags` can be combined with `|`, `&`
+and `^`, and both operands must belong to the same enum, as in
+`Perm.Read | Perm.Write`. Add `@bitflags` before the enum to allow it."),
+    ("E0381", "A variable is read before it has been given a value.
+
+A variable declared without an initializer must be assigned on every path
+before it is read. Assignments inside a loop body do not count after the loop,
+because the body might run zero times; the same holds for an assignment in only
+one branch of an `if`."),
+    ("E0382", "Use of a moved value.
+
+Passing a non-copy value (anything other than `int`, `float` or `bool`) to a
+function moves it, and the original variable can no longer be used. This also
+applies across loop iterations and to values moved in only one branch of an
+`if` or conditional expression, since the value may have been moved. Use the
+value before moving it, or pass a copy."),
+    ("E0384", "Assignment to an immutable variable.
+
+A variable declared with `let` or `const` cannot be assigned after its
+declaration, and neither can compound assignments such as `x += 1` change it.
+Declare it with `var` if it needs to change."),
+    ("E0422", "A struct literal names a struct that is not declared.
+
+Check the spelling of the struct name, or declare the struct."),
+    ("E0424", "`self` was used outside of a method.
+
+`self` refers to the value a method was called on, so it only exists inside a
+method of an `impl` block that takes a `self` parameter. In a free function,
+pass the value as an ordinary parameter instead."),
+    ("E0425", "A name was used where no variable with that name is visible.
+
+A variable is only visible in the block that declares it, after its
+declaration. A loop variable declared in a `for` header cannot be used after
+the loop; declare it before the loop if its final value is needed."),
+    ("E0426", "`break` refers to a label that is not declared.
+
+A `break 'label` must be inside a block introduced with `'label: { ... }`.
+Check the label's spelling and that the break is inside that block."),
+    ("E0435", "A non-constant value was used where a constant is required.
+
+Array sizes and `const` initializers must be computable at compile time from
+literals, other integer constants and arithmetic. Runtime variables cannot be
+used; declare the value with `const` instead."),
+    ("E0499", "A value was borrowed mutably twice at the same time.
+
+Only one `&mut x` borrow may be alive at once. End the first borrow's block
+before taking the second."),
+    ("E0502", "A value was borrowed mutably while another borrow of it was alive.
+
+Any number of `&x` borrows may exist at once, but a `&mut x` borrow must be the
+only one. Borrows last until the end of the block they were taken in, so close
+that block, or take the mutable borrow before the shared ones."),
+    ("E0560", "A struct literal names a field that the struct does not declare.
+
+Check the spelling of the field against the struct's declaration."),
+    ("E0599", "A pattern names a variant that its enum does not declare.
+
+Check the spelling of the variant against the enum's declaration."),
+    ("E0605", "An `as` cast between incompatible types.
+
+`as` only converts between numeric types (and from `bool` to an integer).
+Conversions involving strings, structs or arrays need an explicit function."),
+    ("E0623", "A function returns a reference with a different lifetime than it declares.
+
+In `fn first(a: &'x T, b: &'y T) -> &'x T` the result may only borrow from
+`a`; returning `b` would let the caller keep it after `'y` has ended. Return
+the parameter with the declared lifetime, or change the signature."),
+    ("E0658", "A compiler-internal builtin was used without enabling it.
+
+`__assert_type(expr, \"type\")` checks the type the compiler infers for an
+expression and exists for writing compiler tests. It is rejected unless the
+checker runs with `--type-assertions`, so ordinary programs cannot rely on it."),
+    ("E0716", "A temporary value was passed to a `ref` parameter.
+
+A `ref<T>` parameter borrows an existing place: a variable, a field, an array
+element or a dereferenced pointer. The result of an expression such as
+`a + b` is a temporary with nowhere to borrow from. Store it in a variable and
+pass the variable."),
+    ("E0720", "The return type of a recursive `auto` function cannot be inferred.
+
+An `auto` return type is taken from the values the function returns. When those
+values come from calling the function itself, their type is the very type being
+inferred. Annotate the return type, or add a `return` whose type does not
+depend on the recursive call, such as a base case."),
+    ("E0784", "A struct literal mixes positional and named fields.
+
+A struct can be initialized by field name, `Point { x: 1, y: 2 }`, or by
+position in declaration order, `Point { 1, 2 }`, but not both at once. Name
+every field or none of them."),
+    ("W0012", "A value stored in a struct field is overwritten before it is read.
+
+In `p.x = 1; p.x = 2;` the first value can never be observed. Remove the first
+assignment, or read the field before assigning it again. Fields of variables
+whose address is taken with `&` are not checked, since a pointer may read them."),
+    ("W0017", "A variable is assigned to itself.
+
+`x = x;` has no effect. It is usually a typo for assigning a different
+variable or field. The same warning is given for updates that cannot change
+the value, such as `x += 0` or `x *= 1`."),
+    ("W0020", "An assignment is used as a boolean value.
+
+`if (x = y)` assigns `y` to `x` instead of comparing them. Use `==` to compare
+values."),
+    ("W0023", "A move-typed value is assigned to itself.
+
+For non-copy types, `s = s;` moves `s` into itself, which does nothing useful.
+Remove the assignment."),
+    ("W0024", "A cast may lose data.
+
+Casting a float to an integer truncates it, and casting to a narrower numeric
+type can overflow. Widening casts, such as `int as float` for values the float
+represents exactly, are silent."),
+    ("W0025", "A struct or enum declaration is empty.
+
+A struct with no fields or methods, or an enum with no variants, is usually a
+placeholder left behind by mistake."),
+    ("W0026", "A declaration uses a reserved name prefix.
+
+Names starting with `__fax_` are reserved for identifiers the compiler
+generates, and a user declaration with such a name may collide with them.
+Rename the declaration. The prefix can be changed with `--reserved-prefix`, or
+the check disabled with an empty prefix."),
+    ("W0027", "A bitwise operator is mixed with a comparison without parentheses.
+
+`&`, `|` and `^` bind more loosely than `==`, `<` and the other comparisons,
+so `a & b == c` means `a & (b == c)` rather than `(a & b) == c`. Add
+parentheses to say which grouping is intended."),
+    ("W0028", "A variable in a `for` body has the same name as the loop variable.
+
+`for (let i = 0; i < n; i = i + 1) { let i = ...; }` declares a second `i`
+that hides the loop's own. Changes to the inner `i` do not affect the loop,
+which is rarely what was intended. Give the inner variable a different name."),
+    ("W0029", "A `break` or `continue` comes after a `return`.
+
+Once `return` runs, the function is left, so a `break` or `continue` after it
+in the same block can never execute. Remove the statement, or move it before
+the `return` if the loop was meant to be left instead."),
+    ("W0030", "An `else` follows an `if` branch that always leaves.
+
+When the `if` branch ends with `return`, `break` or `continue`, the code after
+the `if` only runs when the condition is false, so the `else` adds nesting
+without changing behavior. Remove the `else` and move its body after the `if`."),
+    ("W0031", "A function can never be called.
+
+No chain of calls starting from `main` reaches this function. This also covers
+functions that only call each other: each has a caller, but none of them is
+ever entered. Methods count as entry points, since calls to them are not
+tracked. Remove the function, or call it from code that `main` reaches."),
+    ("W0032", "Integer arithmetic may overflow.
+
+Reported with `--check-overflow`, which tracks the smallest and largest value
+each `int` variable can hold. An addition, subtraction or multiplication whose
+result can leave the 64-bit range is flagged, including `x = x + e` inside a
+`for` loop with constant bounds, where the number of iterations is known. Use
+smaller values, fewer iterations, or check the value before the operation."),
+    ("W0033", "A function has too many parameters.
+
+Long parameter lists are hard to call correctly, since arguments of the same
+type are easy to swap. Group parameters that belong together into a struct.
+The limit is 7 by default and can be changed with `--max-params N`."),
+    ("W0034", "A line is indented with both tabs and spaces.
+
+Reported by the lexer with `--check-indentation`. How far such a line appears
+indented depends on the editor's tab width, so it can look aligned with code
+it is not nested in. Indent each line with only tabs or only spaces."),
+    ("W0035", "A `match` arm can never be reached.
+
+An arm is unreachable when an earlier arm already matches the same value, when
+it comes after a `_` arm, or when it is a `_` arm and every variant (both
+values, for a `bool`) already has its own arm above. Remove the arm."),
+    ("W0036", "A reference-counted value is created but never used.
+
+An `rc<T>` that is never read is allocated and dropped without any effect.
+Remove it, or use it. To share an `rc<T>`, pass `value.clone()`, which adds a
+handle to the same value; passing `value` itself moves the handle away."),
+    ("W0037", "`??` is applied to a value that can never be `null`.
+
+`a ?? b` yields `a` unless it is `null`, and `b` otherwise, so it only makes
+sense when `a` is an `optional<T>`. On any other type `b` is never used; remove
+the `??` and its right-hand side."),
+    ("W0038", "A `println` string contains `{}` and is followed by more arguments.
+
+`println` prints each of its arguments in turn, separated by spaces; it does
+not substitute them into the first one. `println(\"x = {}\", x)` prints
+`x = {} 5`. Write `println(\"x =\", x)` instead."),
+];
+
+/// Returns the extended explanation for a diagnostic code such as `E0382`.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS.iter().find(|(c, _)| c.eq_ignore_ascii_case(code)).map(|(_, text)| *text)
+}
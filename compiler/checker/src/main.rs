@@ -1,7 +1,14 @@
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
+use std::sync::{Mutex, OnceLock};
+
+mod deps;
+mod explain;
+mod ranges;
+mod sarif;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Diagnostic {
@@ -17,7 +24,7 @@ struct Span { line: usize, column: usize, length: usize, label: String }
 struct Suggestion { message: String, replacement: String }
 
 #[allow(non_snake_case)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(tag = "type")]
 enum Node {
     Program { body: Vec<Node> },
@@ -25,6 +32,8 @@ enum Node {
         identifier: String, 
         #[serde(rename = "dataType")] data_type: String, 
         #[serde(rename = "isConstant")] is_constant: Option<bool>, 
+        /// `true` for `var`, `false` for `let`; absent in ASTs written before `var` existed.
+        #[serde(default)] mutable: Option<bool>,
         initializer: Option<Box<Node>>, 
         position: Option<Pos> 
     },
@@ -35,81 +44,310 @@ enum Node {
         body: Box<Node>, 
         position: Option<Pos> 
     },
-    StructDeclaration { name: String, fields: Vec<Field>, methods: Vec<Node>, position: Option<Pos> },
-    BlockStatement { body: Vec<Node>, position: Option<Pos> },
+    StructDeclaration {
+        name: String,
+        /// Names of the type parameters of a generic struct such as `Box<T>`.
+        #[serde(default, rename = "typeParams")] type_params: Vec<String>,
+        fields: Vec<Field>,
+        methods: Vec<Node>,
+        position: Option<Pos>,
+    },
+    StructLiteral { name: String, fields: Vec<FieldInit>, position: Option<Pos> },
+    EnumDeclaration { name: String, variants: Vec<Variant>, #[serde(default)] attributes: Vec<String>, position: Option<Pos> },
+    ImplBlock { #[serde(rename = "typeName")] type_name: String, methods: Vec<Node>, position: Option<Pos> },
+    BlockStatement { body: Vec<Node>, value: Option<Box<Node>>, label: Option<String>, position: Option<Pos> },
     ExpressionStatement { expression: Box<Node> },
     AssignmentExpression { left: Box<Node>, right: Box<Node>, position: Option<Pos> },
     CallExpression { callee: Box<Node>, arguments: Vec<Node>, position: Option<Pos> },
     MemberExpression { object: Box<Node>, property: String, position: Option<Pos> },
-    BinaryExpression { operator: String, left: Box<Node>, right: Box<Node>, position: Option<Pos> },
-    IfStatement { test: Box<Node>, consequent: Box<Node>, alternate: Option<Box<Node>>, position: Option<Pos> },
-    WhileStatement { test: Box<Node>, body: Box<Node>, position: Option<Pos> },
+    IndexExpression { object: Box<Node>, index: Box<Node>, position: Option<Pos> },
+    ArrayLiteral { elements: Vec<Node>, position: Option<Pos> },
+    TupleLiteral { elements: Vec<Node>, position: Option<Pos> },
+    /// `let (a, b) = value;`
+    TupleDeclaration { names: Vec<String>, #[serde(rename = "isConstant")] is_constant: Option<bool>, initializer: Box<Node>, position: Option<Pos> },
+    BinaryExpression {
+        operator: String, left: Box<Node>, right: Box<Node>,
+        /// Written inside parentheses in the source.
+        #[serde(default)] parenthesized: bool,
+        position: Option<Pos>,
+    },
+    IfStatement { test: Box<Node>, consequent: Box<Node>, alternate: Option<Box<Node>>, binding: Option<Param>, position: Option<Pos> },
+    WhileStatement { test: Box<Node>, body: Box<Node>, binding: Option<Param>, position: Option<Pos> },
     ForStatement { init: Option<Box<Node>>, test: Option<Box<Node>>, update: Option<Box<Node>>, body: Box<Node>, position: Option<Pos> },
+    MatchStatement { discriminant: Box<Node>, arms: Vec<MatchArm>, position: Option<Pos> },
+    /// `Enum.Variant(name: type)`: an arm pattern that binds the variant's payload.
+    VariantPattern { #[serde(rename = "enumName")] enum_name: String, variant: String, binding: Param, position: Option<Pos> },
     UnaryExpression { operator: String, argument: Box<Node> },
+    CastExpression { expression: Box<Node>, #[serde(rename = "targetType")] target_type: String, position: Option<Pos> },
     Identifier { name: String, position: Option<Pos> },
-    Literal { value: serde_json::Value, position: Option<Pos> },
+    Literal {
+        value: LiteralValue,
+        /// Source position of each character of a string value, when escapes shift them.
+        #[serde(default, skip_serializing_if = "Option::is_none")] offsets: Option<Vec<Pos>>,
+        position: Option<Pos>,
+    },
     ReturnStatement { argument: Option<Box<Node>>, position: Option<Pos> },
-    BreakStatement { position: Option<Pos> },
+    BreakStatement { label: Option<String>, argument: Option<Box<Node>>, position: Option<Pos> },
     ContinueStatement { position: Option<Pos> },
     #[serde(other)] Unknown,
 }
 
+/// The value of a `Literal` node. Floats compare and hash by their bits so that
+/// whole ASTs can be `Eq` and `Hash`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum LiteralValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Null,
+}
+
+impl PartialEq for LiteralValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LiteralValue::Bool(a), LiteralValue::Bool(b)) => a == b,
+            (LiteralValue::Int(a), LiteralValue::Int(b)) => a == b,
+            (LiteralValue::Float(a), LiteralValue::Float(b)) => a.to_bits() == b.to_bits(),
+            (LiteralValue::String(a), LiteralValue::String(b)) => a == b,
+            (LiteralValue::Null, LiteralValue::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LiteralValue {}
+
+impl std::hash::Hash for LiteralValue {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            LiteralValue::Bool(b) => b.hash(state),
+            LiteralValue::Int(i) => i.hash(state),
+            LiteralValue::Float(f) => f.to_bits().hash(state),
+            LiteralValue::String(s) => s.hash(state),
+            LiteralValue::Null => {}
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 struct Field { name: String, #[serde(rename = "type")] field_type: String }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+struct Variant {
+    name: String,
+    #[serde(default)] payload: Option<String>,
+    /// Explicit discriminant, as in `Read = 1`.
+    #[serde(default)] value: Option<Node>,
+    #[serde(default)] position: Option<Pos>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+struct MatchArm { pattern: Node, body: Node, position: Option<Pos> }
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+struct FieldInit {
+    /// `None` for a positional initializer, as in `Point { 1, 2 }`.
+    name: Option<String>,
+    value: Node,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 struct Param { name: String, #[serde(rename = "type")] param_type: String }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 struct Pos { line: usize, column: usize }
 
 struct StructInfo {
-    fields: HashMap<String, String>,
+    /// Type parameters, substituted by the type arguments of each instantiation.
+    type_params: Vec<String>,
+    /// Fields in declaration order.
+    fields: Vec<Field>,
+    /// Method signatures from the struct body and its `impl` blocks, without `self`.
+    methods: HashMap<String, (Vec<String>, String)>,
+}
+
+struct VarInfo {
+    dtype: String,
+    /// Folded value of an integer `const`, usable wherever a constant is required.
+    const_value: Option<i64>,
+    defined_at: Option<Pos>,
+    /// Whether the variable may be assigned after its declaration.
+    mutable: bool,
+}
+
+/// A labeled block being checked, with the type of the first value it is left with.
+struct LabelInfo {
+    name: String,
+    value_type: Option<String>,
 }
 
 struct SymbolTable {
-    scopes: Vec<HashMap<String, String>>,
+    scopes: Vec<HashMap<String, VarInfo>>,
     functions: HashMap<String, (Vec<String>, String)>,
     structs: HashMap<String, StructInfo>,
+    /// Variant names of each declared enum, in declaration order.
+    enums: HashMap<String, Vec<String>>,
+    /// Payload type of each enum variant that carries one, keyed by `Enum.Variant`.
+    payloads: HashMap<String, String>,
+    /// Enums declared `@bitflags`, whose values combine with `|`, `&` and `^`.
+    bitflags: HashSet<String>,
+    labels: Vec<LabelInfo>,
+    /// Value type of each labeled block once checked, so `get_type` can see it.
+    label_types: HashMap<String, String>,
+    /// Where variables of already closed blocks were declared, to explain a later use of them.
+    ended: HashMap<String, Pos>,
+    /// The type whose `impl` block is being checked; `self` parameters have this type.
+    current_impl: Option<String>,
+    /// Names starting with this prefix are kept for compiler-generated code; empty disables the check.
+    reserved_prefix: String,
+    /// Whether `__assert_type(expr, "type")` may be used, for tests of type inference.
+    type_assertions: bool,
+    /// Functions declaring more parameters than this get W0033.
+    max_params: usize,
+    /// Type and position of each value returned by the function being checked, including its tail value.
+    returns: Vec<(String, Pos)>,
+    /// Declared return type of the function being checked; empty outside functions.
+    return_type: String,
+    /// Induction variables of the enclosing `for` loops, innermost last.
+    loop_vars: Vec<(String, Pos)>,
+    /// Type of each expression node already resolved by `get_type`, keyed by the
+    /// node's address. Cleared whenever a name's type may have changed.
+    type_cache: RefCell<HashMap<*const Node, String>>,
+    /// How many types `get_type` computed instead of taking them from `type_cache`.
+    types_computed: Cell<usize>,
 }
 
 impl SymbolTable {
-    fn new() -> Self { SymbolTable { scopes: vec![HashMap::new()], functions: HashMap::new(), structs: HashMap::new() } }
-    fn enter_scope(&mut self) { self.scopes.push(HashMap::new()); }
-    fn exit_scope(&mut self) { self.scopes.pop(); }
-    fn define(&mut self, name: String, dtype: String) {
-        if let Some(scope) = self.scopes.last_mut() { scope.insert(name, dtype); }
+    fn new() -> Self { SymbolTable { scopes: vec![HashMap::new()], functions: HashMap::new(), structs: HashMap::new(), enums: HashMap::new(), payloads: HashMap::new(), bitflags: HashSet::new(), labels: Vec::new(), label_types: HashMap::new(), ended: HashMap::new(), current_impl: None, reserved_prefix: "__fax_".to_string(), type_assertions: false, max_params: 7, returns: Vec::new(), return_type: String::new(), loop_vars: Vec::new(), type_cache: RefCell::default(), types_computed: Cell::new(0) } }
+    fn enter_scope(&mut self) {
+        self.forget_types();
+        self.scopes.push(HashMap::new());
     }
-    fn lookup(&self, name: &str) -> Option<String> {
-        for scope in self.scopes.iter().rev() {
-            if let Some(dtype) = scope.get(name) { return Some(dtype.clone()); }
+    fn exit_scope(&mut self) {
+        self.forget_types();
+        if let Some(scope) = self.scopes.pop() {
+            for (name, info) in scope {
+                if let Some(pos) = info.defined_at { self.ended.insert(name, pos); }
+            }
         }
-        None
     }
+    fn define(&mut self, name: String, dtype: String, defined_at: Option<Pos>) { self.define_const(name, dtype, None, defined_at); }
+    fn define_const(&mut self, name: String, dtype: String, const_value: Option<i64>, defined_at: Option<Pos>) {
+        self.forget_types();
+        if let Some(scope) = self.scopes.last_mut() { scope.insert(name, VarInfo { dtype, const_value, defined_at, mutable: true }); }
+    }
+    /// Marks a binding of the innermost scope as not assignable.
+    fn set_immutable(&mut self, name: &str) {
+        if let Some(info) = self.scopes.last_mut().and_then(|scope| scope.get_mut(name)) { info.mutable = false; }
+    }
+    fn get(&self, name: &str) -> Option<&VarInfo> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+    fn lookup(&self, name: &str) -> Option<String> { self.get(name).map(|info| info.dtype.clone()) }
+    fn lookup_const(&self, name: &str) -> Option<i64> { self.get(name).and_then(|info| info.const_value) }
+    /// Drops cached expression types, e.g. once a declaration shadows a name they used.
+    fn forget_types(&self) { self.type_cache.borrow_mut().clear(); }
+}
+
+/// Set when `--format sarif` is given: diagnostics are collected and written to
+/// stdout as a SARIF log for this artifact uri instead of streamed to stderr.
+static SARIF_URI: OnceLock<String> = OnceLock::new();
+static COLLECTED: Mutex<Vec<Diagnostic>> = Mutex::new(Vec::new());
+/// Warning codes silenced with `--allow` or `FAX_ALLOW`.
+static ALLOWED: OnceLock<HashSet<String>> = OnceLock::new();
+
+fn emit_sarif() {
+    let diags = COLLECTED.lock().unwrap();
+    let uri = SARIF_URI.get().map(String::as_str).unwrap_or_default();
+    println!("{}", serde_json::to_string_pretty(&sarif::to_sarif(&diags, uri)).unwrap());
 }
 
-fn report_error(diag: Diagnostic) -> ! {
-    eprintln!("{}", serde_json::to_string(&diag).unwrap());
-    std::process::exit(1);
+fn report_error(diag: Diagnostic) {
+    COLLECTED.lock().unwrap().push(diag);
 }
 
+fn report_warning(diag: Diagnostic) {
+    if ALLOWED.get().is_some_and(|allowed| allowed.contains(&diag.code)) { return; }
+    COLLECTED.lock().unwrap().push(diag);
+}
+
+/// Whether any collected diagnostic is an error rather than a warning.
+fn has_errors() -> bool {
+    COLLECTED.lock().unwrap().iter().any(|d| !d.code.starts_with('W'))
+}
+
+/// Writes every collected diagnostic to stderr, as one JSON array or, for
+/// `--diagnostic-format ndjson`, as one JSON object per line.
+fn emit_diagnostics(ndjson: bool) {
+    let diags = COLLECTED.lock().unwrap();
+    if ndjson {
+        for diag in diags.iter() { eprintln!("{}", serde_json::to_string(diag).unwrap()); }
+    } else if !diags.is_empty() {
+        eprintln!("{}", serde_json::to_string(&*diags).unwrap());
+    }
+}
+
+fn position_of(node: &Node) -> Option<Pos> {
+    match node {
+        Node::VariableDeclaration { position, .. }
+        | Node::FunctionDeclaration { position, .. }
+        | Node::StructDeclaration { position, .. }
+        | Node::EnumDeclaration { position, .. }
+        | Node::ImplBlock { position, .. }
+        | Node::BlockStatement { position, .. }
+        | Node::AssignmentExpression { position, .. }
+        | Node::ArrayLiteral { position, .. }
+        | Node::TupleLiteral { position, .. }
+        | Node::TupleDeclaration { position, .. }
+        | Node::CallExpression { position, .. }
+        | Node::MemberExpression { position, .. }
+        | Node::IndexExpression { position, .. }
+        | Node::BinaryExpression { position, .. }
+        | Node::CastExpression { position, .. }
+        | Node::StructLiteral { position, .. }
+        | Node::IfStatement { position, .. }
+        | Node::WhileStatement { position, .. }
+        | Node::ForStatement { position, .. }
+        | Node::MatchStatement { position, .. }
+        | Node::VariantPattern { position, .. }
+        | Node::Identifier { position, .. }
+        | Node::Literal { position, .. }
+        | Node::ReturnStatement { position, .. }
+        | Node::BreakStatement { position, .. }
+        | Node::ContinueStatement { position } => position.clone(),
+        Node::ExpressionStatement { expression } => position_of(expression),
+        Node::UnaryExpression { argument, .. } => position_of(argument),
+        Node::Program { .. } | Node::Unknown => None,
+    }
+}
+
+/// The type of `node`, computed once per node while the names in scope stay the same.
 fn get_type(node: &Node, symbols: &SymbolTable) -> String {
+    let key = node as *const Node;
+    if let Some(cached) = symbols.type_cache.borrow().get(&key) { return cached.clone(); }
+    let computed = compute_type(node, symbols);
+    symbols.types_computed.set(symbols.types_computed.get() + 1);
+    symbols.type_cache.borrow_mut().insert(key, computed.clone());
+    computed
+}
+
+fn compute_type(node: &Node, symbols: &SymbolTable) -> String {
     match node {
-        Node::Literal { value, .. } => {
-            if value.is_i64() { "int".to_string() }
-            else if value.is_f64() { "float".to_string() }
-            else if value.is_boolean() { "bool".to_string() }
-            else if value.is_string() { "string".to_string() }
-            else { "unknown".to_string() }
-        }
+        Node::Literal { value, .. } => match value {
+            LiteralValue::Int(_) => "int".to_string(),
+            LiteralValue::Float(_) => "float".to_string(),
+            LiteralValue::Bool(_) => "bool".to_string(),
+            LiteralValue::String(_) => "string".to_string(),
+            LiteralValue::Null => "null".to_string(),
+        },
         Node::Identifier { name, .. } => symbols.lookup(name).unwrap_or("unknown".to_string()),
         Node::UnaryExpression { operator, argument } => {
-            if operator == "&" { return format!("ptr<{}>", get_type(argument, symbols)); }
+            if operator == "&" || operator == "&mut" { return format!("ptr<{}>", get_type(argument, symbols)); }
             if operator == "*" {
-                let inner = get_type(argument, symbols);
-                if inner.starts_with("ptr<") && inner.ends_with(">") {
-                    return inner[4..inner.len()-1].to_string();
-                }
+                if let Some(pointee) = pointee_type(&get_type(argument, symbols)) { return pointee.to_string(); }
             }
             get_type(argument, symbols)
         }
@@ -117,6 +355,12 @@ fn get_type(node: &Node, symbols: &SymbolTable) -> String {
             if matches!(operator.as_str(), "==" | "!=" | "<" | ">" | "<=" | ">=" | "&&" | "||") {
                 return "bool".to_string();
             }
+            if operator == "??" {
+                let lt = get_type(left, symbols);
+                let inner = optional_inner(&lt).unwrap_or(&lt);
+                // Only an optional fallback can leave the result `null`.
+                return if get_type(right, symbols) == format!("optional<{}>", inner) { format!("optional<{}>", inner) } else { inner.to_string() };
+            }
             let lt = get_type(left, symbols);
             let rt = get_type(right, symbols);
             if lt == "float" || rt == "float" { "float".to_string() }
@@ -125,15 +369,50 @@ fn get_type(node: &Node, symbols: &SymbolTable) -> String {
         }
         Node::CallExpression { callee, .. } => {
             if let Node::Identifier { name, .. } = &**callee {
-                if let Some((_, ret)) = symbols.functions.get(name) { return ret.clone(); }
+                // An `auto` return type is only known once the function has been checked.
+                if let Some((_, ret)) = symbols.functions.get(name).filter(|(_, ret)| ret != "auto") { return ret.clone(); }
+            }
+            // A method call takes its return type from the receiver's struct, so chains resolve link by link.
+            if let Node::MemberExpression { object, property, .. } = &**callee {
+                let receiver = get_type(object, symbols);
+                let (base, args) = type_args(&receiver);
+                if let Some(info) = symbols.structs.get(base) {
+                    if let Some((_, ret)) = info.methods.get(property).filter(|(_, ret)| ret != "auto") { return instantiate(ret, &info.type_params, &args); }
+                }
             }
             "unknown".to_string()
         }
+        Node::CastExpression { target_type, .. } => target_type.clone(),
+        // `p[i]` on a `ptr<T>` reads the `T` at offset `i`, like an array element.
+        Node::IndexExpression { object, .. } => {
+            let object_type = get_type(object, symbols);
+            element_type(&object_type)
+                .or_else(|| pointee_type(&object_type).map(str::to_string))
+                .unwrap_or_else(|| "unknown".to_string())
+        }
+        Node::StructLiteral { name, fields, .. } => struct_literal_type(name, fields, symbols),
+        Node::BlockStatement { label: Some(label), .. } => {
+            symbols.label_types.get(label).cloned().unwrap_or_else(|| "unknown".to_string())
+        }
+        // Assignments are expressions: they evaluate to the value that was assigned.
+        Node::AssignmentExpression { right, .. } => get_type(right, symbols),
+        // A literal's length is part of its type, so it can be checked against `array<T, N>`.
+        Node::ArrayLiteral { elements, .. } => {
+            let elem = elements.first().map_or("unknown".to_string(), |e| get_type(e, symbols));
+            format!("array<{}, {}>", elem, elements.len())
+        }
+        Node::TupleLiteral { elements, .. } => {
+            let types: Vec<String> = elements.iter().map(|e| get_type(e, symbols)).collect();
+            format!("tuple<{}>", types.join(", "))
+        }
         Node::MemberExpression { object, property, .. } => {
+            if let Node::Identifier { name, .. } = &**object {
+                if symbols.enums.get(name).is_some_and(|variants| variants.contains(property)) { return name.clone(); }
+            }
             let obj_type = get_type(object, symbols);
-            if let Some(info) = symbols.structs.get(&obj_type) {
-                if let Some(f_type) = info.fields.get(property) {
-                    return f_type.clone();
+            if let Some(fields) = struct_fields(&obj_type, symbols) {
+                if let Some(field) = fields.into_iter().find(|f| &f.name == property) {
+                    return field.field_type;
                 }
             }
             "unknown".to_string()
@@ -142,55 +421,1311 @@ fn get_type(node: &Node, symbols: &SymbolTable) -> String {
     }
 }
 
+/// Folds an integer constant expression, or returns `None` if it depends on runtime values.
+fn eval_const(node: &Node, symbols: &SymbolTable) -> Option<i64> {
+    match node {
+        Node::Literal { value: LiteralValue::Int(n), .. } => Some(*n),
+        Node::Identifier { name, .. } => symbols.lookup_const(name),
+        Node::UnaryExpression { operator, argument } if operator == "-" => eval_const(argument, symbols)?.checked_neg(),
+        Node::BinaryExpression { operator, left, right, .. } => {
+            let (l, r) = (eval_const(left, symbols)?, eval_const(right, symbols)?);
+            match operator.as_str() {
+                "+" => l.checked_add(r),
+                "-" => l.checked_sub(r),
+                "*" => l.checked_mul(r),
+                "/" => l.checked_div(r),
+                "%" => l.checked_rem(r),
+                "|" => Some(l | r),
+                "&" => Some(l & r),
+                "^" => Some(l ^ r),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Splits type arguments at top-level commas, so `array<int, 2>, 3` yields `array<int, 2>` and `3`.
+fn split_type_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, ch) in args.char_indices() {
+        match ch {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(args[start..].trim());
+    parts
+}
+
+/// Splits a type into its name and type arguments, so `Box<int>` yields `Box` and `[int]`.
+fn type_args(dtype: &str) -> (&str, Vec<&str>) {
+    match dtype.split_once('<') {
+        Some((base, rest)) if rest.ends_with('>') => (base, split_type_args(&rest[..rest.len() - 1])),
+        _ => (dtype, vec![]),
+    }
+}
+
+/// Replaces each of `params` in `dtype` with the matching type argument, so
+/// with `T` as `int` the field type `ptr<T>` becomes `ptr<int>`.
+fn instantiate(dtype: &str, params: &[String], args: &[&str]) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = String::new();
+    let mut rest = dtype;
+    while let Some(start) = rest.find(is_word) {
+        out.push_str(&rest[..start]);
+        let len = rest[start..].find(|c: char| !is_word(c)).unwrap_or(rest.len() - start);
+        let word = &rest[start..start + len];
+        out.push_str(params.iter().position(|p| p == word).and_then(|i| args.get(i).copied()).unwrap_or(word));
+        rest = &rest[start + len..];
+    }
+    out + rest
+}
+
+/// Fields of the struct type `dtype` with its type arguments substituted, or
+/// `None` if `dtype` is not a struct instantiated with the right number of them.
+fn struct_fields(dtype: &str, symbols: &SymbolTable) -> Option<Vec<Field>> {
+    let (base, args) = type_args(dtype);
+    let info = symbols.structs.get(base)?;
+    if args.len() != info.type_params.len() { return None; }
+    Some(info.fields.iter().map(|f| Field { name: f.name.clone(), field_type: instantiate(&f.field_type, &info.type_params, &args) }).collect())
+}
+
+/// Type of a struct literal. A generic struct takes each type argument from a
+/// field declared with that bare parameter, so `Box { value: 1 }` is a `Box<int>`.
+fn struct_literal_type(name: &str, inits: &[FieldInit], symbols: &SymbolTable) -> String {
+    let Some(info) = symbols.structs.get(name).filter(|info| !info.type_params.is_empty()) else { return name.to_string() };
+    let positional = inits.iter().any(|i| i.name.is_none());
+    let mut args = Vec::new();
+    for param in &info.type_params {
+        let value = info.fields.iter().enumerate().filter(|(_, f)| &f.field_type == param).find_map(|(i, f)| {
+            if positional { inits.get(i) } else { inits.iter().find(|init| init.name.as_ref() == Some(&f.name)) }
+        });
+        let arg = value.map_or("unknown".to_string(), |init| get_type(&init.value, symbols));
+        if arg == "unknown" { return arg; }
+        args.push(arg);
+    }
+    format!("{}<{}>", name, args.join(", "))
+}
+
+/// Element types of a tuple type `tuple<T, U, ...>`.
+fn tuple_elements(dtype: &str) -> Option<Vec<&str>> {
+    dtype.strip_prefix("tuple<")?.strip_suffix('>').map(split_type_args)
+}
+
+/// Element type of an array type: `T[]`, `array<T>` or `array<T, N>`.
+fn element_type(dtype: &str) -> Option<String> {
+    if let Some(elem) = dtype.strip_suffix("[]") { return Some(elem.to_string()); }
+    let inner = dtype.strip_prefix("array<")?.strip_suffix('>')?;
+    Some(split_type_args(inner)[0].to_string())
+}
+
+/// The `T` of a `ptr<T>` type.
+fn pointee_type(dtype: &str) -> Option<&str> {
+    dtype.strip_prefix("ptr<")?.strip_suffix('>')
+}
+
+/// Whether `node` names a memory location that a `ref` can point at, rather
+/// than a temporary produced by evaluating an expression.
+fn is_place(node: &Node) -> bool {
+    match node {
+        Node::Identifier { .. } | Node::MemberExpression { .. } | Node::IndexExpression { .. } => true,
+        Node::UnaryExpression { operator, .. } => operator == "*",
+        _ => false,
+    }
+}
+
+/// Validates the type arguments of a struct type and the sizes of any
+/// fixed-size arrays (`array<T, N>`) in a type annotation.
+fn check_type_annotation(dtype: &str, pos: &Pos, symbols: &SymbolTable) {
+    let (base, args) = type_args(dtype);
+    if let Some(info) = symbols.structs.get(base).filter(|info| info.type_params.len() != args.len()) {
+        let (expected, found) = (info.type_params.len(), args.len());
+        report_error(Diagnostic {
+            code: "E0107".to_string(),
+            message: format!("struct `{}` takes {} type argument{} but {} {} supplied", base, expected, if expected == 1 { "" } else { "s" }, found, if found == 1 { "was" } else { "were" }),
+            primary_span: Span { line: pos.line, column: pos.column, length: dtype.len(), label: format!("expected {} type argument{}", expected, if expected == 1 { "" } else { "s" }) },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: (expected > 0).then(|| format!("write the type as `{}<{}>`", base, info.type_params.join(", "))),
+        });
+        return;
+    }
+    let Some(inner) = dtype.strip_prefix("array<").and_then(|t| t.strip_suffix('>')) else { return };
+    let args = split_type_args(inner);
+    check_type_annotation(args[0], pos, symbols);
+    let Some(size) = args.get(1) else { return };
+    let value = size.parse::<i64>().ok().or_else(|| symbols.lookup_const(size));
+    match value {
+        Some(n) if n > 0 => {}
+        Some(n) => report_error(Diagnostic {
+            code: "E0080".to_string(),
+            message: "array size must be a positive integer".to_string(),
+            primary_span: Span { line: pos.line, column: pos.column, length: size.len(), label: format!("size evaluates to `{}`", n) },
+            secondary_spans: vec![], suggestion: None, note: None,
+        }),
+        None => report_error(Diagnostic {
+            code: "E0435".to_string(),
+            message: "attempt to use a non-constant value in a constant".to_string(),
+            primary_span: Span { line: pos.line, column: pos.column, length: size.len(), label: format!("`{}` is not a constant", size) },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some("array sizes must be integer literals or `const` values".to_string()),
+        }),
+    }
+}
+
+/// Rejects a non-`bool` condition of an `if`, `while` or `for`.
+fn check_condition_type(test: &Node, symbols: &SymbolTable) {
+    let found = get_type(test, symbols);
+    if found == "bool" || found == "unknown" { return; }
+    let p = position_of(test).unwrap_or(Pos { line: 0, column: 0 });
+    report_error(Diagnostic {
+        code: "E0308".to_string(),
+        message: "mismatched types".to_string(),
+        primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("expected `bool` for condition, found `{}`", found) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some("conditions are not converted to `bool` implicitly; compare the value instead, e.g. `n != 0`".to_string()),
+    });
+}
+
+/// `__assert_type(expr, "type")`: fails unless `expr` has exactly the named
+/// type. Only available with `--type-assertions`, so programs cannot depend on it.
+fn check_type_assertion(arguments: &[Node], position: &Option<Pos>, symbols: &SymbolTable) {
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    if !symbols.type_assertions {
+        report_error(Diagnostic {
+            code: "E0658".to_string(),
+            message: "`__assert_type` is only available in compiler tests".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 13, label: "type assertions are not enabled".to_string() },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some("pass `--type-assertions` to the checker to enable them".to_string()),
+        });
+    }
+    let [expr, Node::Literal { value: LiteralValue::String(expected), .. }] = arguments else {
+        report_error(Diagnostic {
+            code: "E0061".to_string(),
+            message: format!("function `__assert_type` expected 2 arguments, got {}", arguments.len()),
+            primary_span: Span { line: p.line, column: p.column, length: 13, label: "expected an expression and a type name string".to_string() },
+            secondary_spans: vec![], suggestion: None, note: None,
+        });
+        return;
+    };
+    let found = get_type(expr, symbols);
+    if &found == expected { return; }
+    let at = position_of(expr).unwrap_or(p);
+    report_error(Diagnostic {
+        code: "E0308".to_string(),
+        message: "type assertion failed".to_string(),
+        primary_span: Span { line: at.line, column: at.column, length: 1, label: format!("expected `{}`, found `{}`", expected, found) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: None,
+    });
+}
+
+/// Rejects using the result of a `void` call where a value is required.
+fn check_not_void(node: &Node, symbols: &SymbolTable) {
+    if get_type(node, symbols) != "void" { return; }
+    let p = position_of(node).unwrap_or(Pos { line: 0, column: 0 });
+    let length = match node {
+        Node::CallExpression { callee, .. } => match &**callee {
+            Node::Identifier { name, .. } => name.len() + 2,
+            _ => 1,
+        },
+        _ => 1,
+    };
+    report_error(Diagnostic {
+        code: "E0308".to_string(),
+        message: "`void` is not a value".to_string(),
+        primary_span: Span { line: p.line, column: p.column, length, label: "this call returns `void`".to_string() },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some("call the function as a statement instead".to_string()),
+    });
+}
+
+/// Name and signature of a method declaration; `self` is not part of the parameter list.
+fn method_signature(method: &Node) -> Option<(String, (Vec<String>, String))> {
+    let Node::FunctionDeclaration { name, params, return_type, .. } = method else { return None };
+    let p_types = params.iter().filter(|p| p.name != "self").map(|p| p.param_type.clone()).collect();
+    Some((name.clone(), (p_types, return_type.clone())))
+}
+
+/// Adds the methods of `impl type_name { ... }` to the struct's method set.
+fn register_impl_methods(type_name: &str, methods: &[Node], position: &Option<Pos>, symbols: &mut SymbolTable) {
+    let Some(info) = symbols.structs.get_mut(type_name) else {
+        let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+        report_error(Diagnostic {
+            code: "E0116".to_string(),
+            message: format!("cannot define inherent `impl` for unknown type `{}`", type_name),
+            primary_span: Span { line: p.line, column: p.column, length: type_name.len() + 5, label: "impl for a type that is not declared in this module".to_string() },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some("`impl` blocks can only add methods to structs declared in the same module".to_string()),
+        });
+        return;
+    };
+    for method in methods {
+        let Some((name, signature)) = method_signature(method) else { continue };
+        if info.methods.contains_key(&name) {
+            let p = position_of(method).unwrap_or(Pos { line: 0, column: 0 });
+            report_error(Diagnostic {
+                code: "E0201".to_string(),
+                message: format!("duplicate definitions with name `{}`", name),
+                primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("`{}` is already defined for `{}`", name, type_name) },
+                secondary_spans: vec![], suggestion: None, note: None,
+            });
+        }
+        info.methods.insert(name, signature);
+    }
+}
+
+/// Checks explicit discriminants: each must be a constant integer used by only
+/// one variant, and a `@bitflags` enum needs a distinct power of two for every variant.
+fn check_discriminants(name: &str, variants: &[Variant], position: &Option<Pos>, symbols: &SymbolTable) {
+    let bitflags = symbols.bitflags.contains(name);
+    let mut seen: HashMap<i64, (&str, Pos)> = HashMap::new();
+    for v in variants {
+        let p = v.position.clone().or(position.clone()).unwrap_or(Pos { line: 0, column: 0 });
+        let span = |label: String| Span { line: p.line, column: p.column, length: v.name.len(), label };
+        let value = match &v.value {
+            None if bitflags => {
+                report_error(Diagnostic {
+                    code: "E0080".to_string(),
+                    message: format!("bitflags variant `{}` has no discriminant", v.name),
+                    primary_span: span("expected `= <power of two>` after this variant".to_string()),
+                    secondary_spans: vec![], suggestion: None,
+                    note: Some(format!("`{}` is `@bitflags`, so each variant needs its own bit, such as `= 1`, `= 2` or `= 4`", name)),
+                });
+                continue;
+            }
+            None => continue,
+            Some(expr) => match eval_const(expr, symbols) {
+                Some(value) => value,
+                None => {
+                    report_error(Diagnostic {
+                        code: "E0080".to_string(),
+                        message: format!("discriminant of `{}.{}` is not a constant integer", name, v.name),
+                        primary_span: span("cannot be evaluated at compile time".to_string()),
+                        secondary_spans: vec![], suggestion: None, note: None,
+                    });
+                    continue;
+                }
+            },
+        };
+        if bitflags && (value <= 0 || value & (value - 1) != 0) {
+            report_error(Diagnostic {
+                code: "E0080".to_string(),
+                message: format!("discriminant of bitflags variant `{}` is not a power of two", v.name),
+                primary_span: span(format!("`{}` is not a single bit", value)),
+                secondary_spans: vec![], suggestion: None,
+                note: Some("combinations of flags are written `A | B` where they are used, not declared as variants".to_string()),
+            });
+        }
+        if let Some((first, first_pos)) = seen.get(&value) {
+            report_error(Diagnostic {
+                code: "E0081".to_string(),
+                message: format!("discriminant value `{}` assigned more than once", value),
+                primary_span: span(format!("`{}` assigned here", value)),
+                secondary_spans: vec![Span { line: first_pos.line, column: first_pos.column, length: first.len(), label: format!("first assigned to `{}` here", first) }],
+                suggestion: None, note: None,
+            });
+        } else {
+            seen.insert(value, (&v.name, p.clone()));
+        }
+    }
+}
+
+/// Rejects `|`, `&` and `^` on enum values unless both sides are the same `@bitflags` enum.
+fn check_enum_operator(operator: &str, left: &Node, right: &Node, position: &Option<Pos>, symbols: &SymbolTable) {
+    if !matches!(operator, "&" | "|" | "^") { return; }
+    let (lt, rt) = (get_type(left, symbols), get_type(right, symbols));
+    if !symbols.enums.contains_key(&lt) && !symbols.enums.contains_key(&rt) { return; }
+    if lt == rt && symbols.bitflags.contains(&lt) { return; }
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    let enum_name = if symbols.enums.contains_key(&lt) { &lt } else { &rt };
+    report_error(Diagnostic {
+        code: "E0369".to_string(),
+        message: format!("no implementation for `{} {} {}`", lt, operator, rt),
+        primary_span: Span { line: p.line, column: p.column, length: operator.len(), label: format!("`{}` cannot be applied to these operands", operator) },
+        secondary_spans: vec![], suggestion: None,
+        note: Some(if lt == rt {
+            format!("declare `{}` with `@bitflags` to combine its values", enum_name)
+        } else {
+            format!("both operands must be values of the `@bitflags` enum `{}`", enum_name)
+        }),
+    });
+}
+
+fn warn_empty_declaration(kind: &str, name: &str, position: &Option<Pos>) {
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    let contents = if kind == "enum" { "no variants" } else { "no fields or methods" };
+    report_warning(Diagnostic {
+        code: "W0025".to_string(),
+        message: "empty declaration".to_string(),
+        primary_span: Span { line: p.line, column: p.column, length: kind.len() + 1 + name.len(), label: format!("`{}` has {}", name, contents) },
+        secondary_spans: vec![], suggestion: None, note: None,
+    });
+}
+
+/// The `T` of an `optional<T>` type.
+fn optional_inner(t: &str) -> Option<&str> {
+    t.strip_prefix("optional<").and_then(|t| t.strip_suffix('>'))
+}
+
+/// Whether a value of type `value` can be stored where `target` is expected:
+/// the same type, or `T` or `null` where an `optional<T>` is expected.
+fn is_assignable(target: &str, value: &str) -> bool {
+    if target == value || optional_inner(target).is_some_and(|inner| value == "null" || inner == value) { return true; }
+    if let (Some(t), Some(v)) = (tuple_elements(target), tuple_elements(value)) {
+        return t.len() == v.len() && t.iter().zip(&v).all(|(t, v)| *v == "unknown" || is_assignable(t, v));
+    }
+    // Arrays agree on their element type here; lengths are compared by `check_array_length`.
+    match (element_type(target), element_type(value)) {
+        (Some(t), Some(v)) => v == "unknown" || is_assignable(&t, &v),
+        _ => false,
+    }
+}
+
+/// The `N` of an `array<T, N>` type, resolving `const` sizes; `None` for arrays without a length.
+fn fixed_length(dtype: &str, symbols: &SymbolTable) -> Option<i64> {
+    let inner = dtype.strip_prefix("array<")?.strip_suffix('>')?;
+    let size = *split_type_args(inner).get(1)?;
+    size.parse::<i64>().ok().or_else(|| symbols.lookup_const(size))
+}
+
+/// Rejects storing a fixed-size array where one of a different length is expected.
+fn check_array_length(target: &str, value: &str, name: &str, pos: &Pos, symbols: &SymbolTable) {
+    let (Some(expected), Some(found)) = (fixed_length(target, symbols), fixed_length(value, symbols)) else { return };
+    if expected == found { return; }
+    report_error(Diagnostic {
+        code: "E0308".to_string(),
+        message: "mismatched array length".to_string(),
+        primary_span: Span { line: pos.line, column: pos.column, length: name.len(), label: format!("expected an array of {} elements, found {}", expected, found) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some(format!("`{}` holds exactly {} elements", target, expected)),
+    });
+}
+
+/// Rejects an array literal whose elements do not all have the type of the first one.
+fn check_array_elements(elements: &[Node], symbols: &SymbolTable) {
+    let Some(first) = elements.first() else { return };
+    let expected = get_type(first, symbols);
+    if expected == "unknown" { return; }
+    let Some((element, found)) = elements.iter().skip(1)
+        .map(|e| (e, get_type(e, symbols)))
+        .find(|(_, t)| t != "unknown" && !is_assignable(&expected, t)) else { return };
+    let p = position_of(element).unwrap_or(Pos { line: 0, column: 0 });
+    let f = position_of(first).unwrap_or(Pos { line: 0, column: 0 });
+    report_error(Diagnostic {
+        code: "E0308".to_string(),
+        message: "mismatched types".to_string(),
+        primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("expected `{}`, found `{}`", expected, found) },
+        secondary_spans: vec![Span { line: f.line, column: f.column, length: 1, label: format!("the first element has type `{}`", expected) }],
+        suggestion: None,
+        note: Some("all elements of an array literal must have the same type".to_string()),
+    });
+}
+
+/// Rejects indexing an array or pointer with anything but an `int`.
+fn check_index_type(object: &Node, index: &Node, position: &Option<Pos>, symbols: &SymbolTable) {
+    let object_type = get_type(object, symbols);
+    if element_type(&object_type).is_none() && pointee_type(&object_type).is_none() { return; }
+    let index_type = get_type(index, symbols);
+    if index_type == "int" || index_type == "unknown" { return; }
+    let p = position_of(index).or(position.clone()).unwrap_or(Pos { line: 0, column: 0 });
+    report_error(Diagnostic {
+        code: "E0277".to_string(),
+        message: format!("the type `{}` cannot be indexed by `{}`", object_type, index_type),
+        primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("expected `int`, found `{}`", index_type) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some("array and pointer subscripts must be `int`".to_string()),
+    });
+}
+
+/// Rejects assigning to a `let` or `const` binding after its declaration.
+fn check_assignable_binding(name: &str, p: &Pos, symbols: &SymbolTable) {
+    let Some(info) = symbols.get(name).filter(|info| !info.mutable) else { return };
+    let secondary_spans = info.defined_at.iter()
+        .map(|d| Span { line: d.line, column: d.column, length: name.len(), label: format!("`{}` is declared immutable here", name) })
+        .collect();
+    report_error(Diagnostic {
+        code: "E0384".to_string(),
+        message: format!("cannot assign twice to immutable variable `{}`", name),
+        primary_span: Span { line: p.line, column: p.column, length: name.len(), label: "cannot assign twice to immutable variable".to_string() },
+        secondary_spans,
+        suggestion: Some(Suggestion { message: format!("declare `{}` with `var` to make it mutable", name), replacement: "var".to_string() }),
+        note: None,
+    });
+}
+
+/// Source position of character `index` of a string literal's value.
+fn string_char_pos(offsets: &Option<Vec<Pos>>, start: &Pos, index: usize) -> Pos {
+    match offsets.as_ref().and_then(|o| o.get(index)) {
+        Some(p) => p.clone(),
+        None => Pos { line: start.line, column: start.column + 1 + index },
+    }
+}
+
+/// Warns about a `{}` in the first string given to `println` with more
+/// arguments: `println` prints its arguments separated by spaces, so the
+/// placeholder is printed as is rather than replaced by the next argument.
+fn check_format_string(arguments: &[Node]) {
+    let Some((Node::Literal { value: LiteralValue::String(text), offsets, position }, args)) = arguments.split_first() else { return };
+    if args.is_empty() { return; }
+    let chars: Vec<char> = text.chars().collect();
+    let Some(index) = chars.windows(2).position(|pair| pair == ['{', '}']) else { return };
+    let start = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    let p = string_char_pos(offsets, &start, index);
+    report_warning(Diagnostic {
+        code: "W0038".to_string(),
+        message: "`println` does not fill in `{}` placeholders".to_string(),
+        primary_span: Span { line: p.line, column: p.column, length: 2, label: "printed as is".to_string() },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some("`println` prints its arguments separated by spaces: `println(\"x =\", x)` prints `x = 5`".to_string()),
+    });
+}
+
+/// Hint for storing an `optional<T>` where a `T` is expected.
+fn unwrap_note(target: &str, value: &str) -> Option<String> {
+    (optional_inner(value) == Some(target)).then(|| "the value may be `null`; check it with `if (x != null)` or `if let` first".to_string())
+}
+
+/// Hint for passing `null`, or a value that may be `null`, to a parameter that
+/// cannot hold it. Pointers are non-null unless declared `ptr<T>?`.
+fn null_argument_note(param_type: &str, arg_type: &str) -> Option<String> {
+    if pointee_type(param_type).is_some() && (arg_type == "null" || optional_inner(arg_type) == Some(param_type)) {
+        return Some(format!("`{}` parameters cannot be `null`; check the pointer with `if (p != null)` first, or declare the parameter as `{}?`", param_type, param_type));
+    }
+    unwrap_note(param_type, arg_type)
+}
+
+/// Checks `a ?? b`: `a` should be an `optional<T>`, and `b` must be a `T`
+/// (or another `optional<T>`) so both sides agree on the result type.
+fn check_null_coalesce(left: &Node, right: &Node, position: &Option<Pos>, symbols: &SymbolTable) {
+    let (lt, rt) = (get_type(left, symbols), get_type(right, symbols));
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    let inner = match optional_inner(&lt) {
+        Some(inner) => inner,
+        None if lt == "unknown" => return,
+        None => {
+            report_warning(Diagnostic {
+                code: "W0037".to_string(),
+                message: "`??` on a value that is never `null`".to_string(),
+                primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("`{}` is not an optional type", lt) },
+                secondary_spans: vec![],
+                suggestion: None,
+                note: Some("the right-hand side of `??` is never used".to_string()),
+            });
+            &lt
+        }
+    };
+    if rt == "unknown" || (rt != "null" && is_assignable(&format!("optional<{}>", inner), &rt)) { return; }
+    let at = position_of(right).unwrap_or(p);
+    report_error(Diagnostic {
+        code: "E0308".to_string(),
+        message: "mismatched types".to_string(),
+        primary_span: Span { line: at.line, column: at.column, length: 1, label: format!("expected `{}`, found `{}`", inner, rt) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some(format!("the fallback of `??` must have the type of the value it replaces, `{}`", inner)),
+    });
+}
+
+/// The variable tested by `x != null` or `null != x`.
+fn null_checked(test: &Node) -> Option<&str> {
+    let Node::BinaryExpression { operator, left, right, .. } = test else { return None };
+    if operator != "!=" { return None; }
+    match (&**left, &**right) {
+        (Node::Identifier { name, .. }, Node::Literal { value: LiteralValue::Null, .. })
+        | (Node::Literal { value: LiteralValue::Null, .. }, Node::Identifier { name, .. }) => Some(name),
+        _ => None,
+    }
+}
+
+/// Checks `if let` / `while let`: the producer must be an `optional<T>`, and the
+/// binding has type `T` inside the body only.
+fn check_let_binding(binding: &Param, producer: &Node, body: &Node, position: &Option<Pos>, symbols: &mut SymbolTable) {
+    let p = position_of(producer).or(position.clone()).unwrap_or(Pos { line: 0, column: 0 });
+    let producer_type = get_type(producer, symbols);
+    let value_type = if producer_type == "unknown" {
+        "unknown".to_string()
+    } else if let Some(inner) = optional_inner(&producer_type) {
+        inner.to_string()
+    } else {
+        report_error(Diagnostic {
+            code: "E0308".to_string(),
+            message: "mismatched types".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("expected `optional<_>`, found `{}`", producer_type) },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some("`let` conditions bind the value of an `optional`; use a plain condition for other values".to_string()),
+        });
+        "unknown".to_string()
+    };
+    if binding.param_type != "auto" && value_type != "unknown" && binding.param_type != value_type {
+        report_error(Diagnostic {
+            code: "E0308".to_string(),
+            message: "mismatched types".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("expected `optional<{}>`, found `optional<{}>`", binding.param_type, value_type) },
+            secondary_spans: vec![], suggestion: None, note: None,
+        });
+    }
+    let dtype = if binding.param_type == "auto" { value_type } else { binding.param_type.clone() };
+    check_reserved_name(&binding.name, position, symbols);
+    symbols.enter_scope();
+    symbols.define(binding.name.clone(), dtype, position.clone());
+    check(body, symbols);
+    symbols.exit_scope();
+}
+
+/// Warns when a bitwise operator has an ungrouped comparison as an operand, as in
+/// `a & b == c`, which parses as `a & (b == c)`.
+fn check_precedence(operator: &str, left: &Node, right: &Node, position: &Option<Pos>) {
+    if !matches!(operator, "&" | "|" | "^") { return; }
+    let ungrouped_comparison = |node: &Node| match node {
+        Node::BinaryExpression { operator, parenthesized: false, .. } => matches!(operator.as_str(), "==" | "!=" | "<" | ">" | "<=" | ">="),
+        _ => false,
+    };
+    let (as_parsed, alternative) = if ungrouped_comparison(right) {
+        (format!("a {} (b == c)", operator), format!("(a {} b) == c", operator))
+    } else if ungrouped_comparison(left) {
+        (format!("(a == b) {} c", operator), format!("a == (b {} c)", operator))
+    } else {
+        return;
+    };
+    let p = position_of(right).or(position.clone()).unwrap_or(Pos { line: 0, column: 0 });
+    report_warning(Diagnostic {
+        code: "W0027".to_string(),
+        message: format!("`{}` has lower precedence than comparisons", operator),
+        primary_span: Span { line: p.line, column: p.column, length: 1, label: "this comparison is evaluated first".to_string() },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some(format!("add parentheses to make the grouping explicit: `{}` or `{}`", as_parsed, alternative)),
+    });
+}
+
+/// Checks every returned value against the declared return type, or against the first
+/// returned value when the return type is `auto`. Returns the function's resolved return type.
+fn unify_returns(declared: &str, returns: &[(String, Pos)]) -> String {
+    let mut expected: Option<(String, Pos)> = (declared != "auto").then(|| (declared.to_string(), Pos { line: 0, column: 0 }));
+    for (found, p) in returns {
+        if found == "unknown" { continue; }
+        let Some((want, first)) = &expected else {
+            expected = Some((found.clone(), p.clone()));
+            continue;
+        };
+        if is_assignable(want, found) { continue; }
+        let (label, secondary_spans) = if declared == "auto" {
+            (format!("expected `{}` because of an earlier return, found `{}`", want, found),
+             vec![Span { line: first.line, column: first.column, length: 1, label: format!("return type inferred as `{}` here", want) }])
+        } else {
+            (format!("expected `{}` because of return type, found `{}`", want, found), vec![])
+        };
+        report_error(Diagnostic {
+            code: "E0308".to_string(),
+            message: "mismatched types".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 1, label },
+            secondary_spans,
+            suggestion: None,
+            note: tuple_arity_note(want, found),
+        });
+    }
+    match expected {
+        Some((t, _)) => t,
+        None if returns.is_empty() => "void".to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Hint for returning a tuple with a different number of elements than expected.
+fn tuple_arity_note(want: &str, found: &str) -> Option<String> {
+    let (want, found) = (tuple_elements(want)?, tuple_elements(found)?);
+    (want.len() != found.len()).then(|| format!("the function returns {} values, but this `return` has {}", want.len(), found.len()))
+}
+
+/// Binds each name of `let (a, b) = value;` to the matching element of the tuple.
+fn check_tuple_declaration(names: &[String], initializer: &Node, position: &Option<Pos>, symbols: &mut SymbolTable) {
+    let found = get_type(initializer, symbols);
+    let elements: Vec<String> = tuple_elements(&found).unwrap_or_default().into_iter().map(str::to_string).collect();
+    if found != "unknown" && elements.len() != names.len() {
+        let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+        let label = if elements.is_empty() {
+            format!("expected a tuple, found `{}`", found)
+        } else {
+            format!("expected a tuple of {} elements, found `{}`", names.len(), found)
+        };
+        report_error(Diagnostic {
+            code: "E0308".to_string(),
+            message: "mismatched types".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 3, label },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some(format!("the pattern binds {} names, so the value must be a tuple with {} elements", names.len(), names.len())),
+        });
+    }
+    for (i, name) in names.iter().enumerate() {
+        check_reserved_name(name, position, symbols);
+        let dtype = elements.get(i).filter(|_| elements.len() == names.len()).cloned().unwrap_or_else(|| "unknown".to_string());
+        symbols.define(name.clone(), dtype, position.clone());
+    }
+}
+
+/// Reports an `auto` function whose returned values could not be typed because
+/// they depend on a call to the function itself.
+fn check_recursive_inference(name: &str, body: &Node, fn_pos: &Pos) {
+    let body = serde_json::to_value(body).unwrap_or_default();
+    let Some(call) = deps::find_call(&body, name) else { return };
+    let p: Pos = serde_json::from_value(call["position"].clone()).unwrap_or(Pos { line: 0, column: 0 });
+    report_error(Diagnostic {
+        code: "E0720".to_string(),
+        message: format!("cannot infer the return type of recursive function `{}`", name),
+        primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("the type of this call is the return type of `{}` being inferred", name) },
+        secondary_spans: vec![Span { line: fn_pos.line, column: fn_pos.column, length: 2, label: "declared with an `auto` return type".to_string() }],
+        suggestion: None,
+        note: Some(format!("annotate the return type, e.g. `fn {}(...) -> int`", name)),
+    });
+}
+
+/// Makes the inferred return type of an `auto` function or method visible to later calls.
+fn record_inferred_return(name: &str, resolved: String, symbols: &mut SymbolTable) {
+    let signature = match &symbols.current_impl {
+        Some(impl_type) => symbols.structs.get_mut(impl_type).and_then(|info| info.methods.get_mut(name)),
+        None => symbols.functions.get_mut(name),
+    };
+    if let Some((_, ret)) = signature { *ret = resolved; }
+    symbols.forget_types();
+}
+
+/// Warns when a declaration in a `for` body reuses the name of that loop's induction variable.
+fn check_loop_var_shadowing(name: &str, p: &Pos, symbols: &SymbolTable) {
+    let Some((_, loop_pos)) = symbols.loop_vars.iter().rev().find(|(var, _)| var == name) else { return };
+    report_warning(Diagnostic {
+        code: "W0028".to_string(),
+        message: format!("`{}` shadows the loop variable", name),
+        primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("this `{}` hides the loop's `{}`", name, name) },
+        secondary_spans: vec![Span { line: loop_pos.line, column: loop_pos.column, length: name.len(), label: "loop variable declared here".to_string() }],
+        suggestion: None,
+        note: Some("updates to the inner variable do not affect the loop; use a different name".to_string()),
+    });
+}
+
+/// The `return`, `break` or `continue` that `node` unconditionally ends with, if any.
+fn terminator(node: &Node) -> Option<(&'static str, Pos)> {
+    let keyword = match node {
+        Node::BlockStatement { body, value: None, .. } => return body.last().and_then(terminator),
+        Node::ReturnStatement { .. } => "return",
+        Node::BreakStatement { .. } => "break",
+        Node::ContinueStatement { .. } => "continue",
+        _ => return None,
+    };
+    Some((keyword, position_of(node).unwrap_or(Pos { line: 0, column: 0 })))
+}
+
+/// Warns about an `else` whose `if` branch always leaves with `return`, `break` or `continue`.
+fn check_redundant_else(consequent: &Node, alternate: &Node) {
+    let Some((keyword, end)) = terminator(consequent) else { return };
+    let p = position_of(alternate).unwrap_or(Pos { line: 0, column: 0 });
+    report_warning(Diagnostic {
+        code: "W0030".to_string(),
+        message: format!("unnecessary `else` after `{}`", keyword),
+        primary_span: Span { line: p.line, column: p.column, length: 1, label: "this `else` branch can follow the `if` unindented".to_string() },
+        secondary_spans: vec![Span { line: end.line, column: end.column, length: keyword.len(), label: format!("the `if` branch always ends with this `{}`", keyword) }],
+        suggestion: None,
+        note: Some("remove the `else` and move its body after the `if`".to_string()),
+    });
+}
+
+/// The values a `match` on `type_name` must cover: an enum's variants, with
+/// `bool` treated as an enum whose variants are `true` and `false`.
+fn match_variants(type_name: &str, symbols: &SymbolTable) -> Option<Vec<String>> {
+    if type_name == "bool" { return Some(vec!["true".to_string(), "false".to_string()]); }
+    symbols.enums.get(type_name).cloned()
+}
+
+/// The value an arm's pattern stands for, used to spot arms that repeat an
+/// earlier one; `None` for the `_` wildcard. Rejects patterns of another type.
+fn match_pattern(pattern: &Node, scrutinee: &str, variants: &Option<Vec<String>>, symbols: &SymbolTable) -> Option<String> {
+    let p = position_of(pattern).unwrap_or(Pos { line: 0, column: 0 });
+    let found = match pattern {
+        Node::Identifier { name, .. } if name == "_" => return None,
+        Node::MemberExpression { object, property, .. } => match &**object {
+            Node::Identifier { name, .. } if symbols.enums.contains_key(name) => {
+                check_variant_exists(name, property, &p, scrutinee, variants);
+                name.clone()
+            }
+            _ => get_type(pattern, symbols),
+        },
+        Node::VariantPattern { enum_name, variant, binding, .. } => {
+            check_variant_exists(enum_name, variant, &p, scrutinee, variants);
+            let payload = symbols.payloads.get(&format!("{}.{}", enum_name, variant));
+            if let Some(payload) = payload.filter(|t| binding.param_type != "auto" && binding.param_type != **t) {
+                report_error(Diagnostic {
+                    code: "E0308".to_string(),
+                    message: "mismatched types".to_string(),
+                    primary_span: Span { line: p.line, column: p.column, length: enum_name.len() + variant.len() + 1, label: format!("expected `{}`, found `{}`", payload, binding.param_type) },
+                    secondary_spans: vec![],
+                    suggestion: None,
+                    note: Some(format!("`{}.{}` carries a value of type `{}`, so `{}` must have that type", enum_name, variant, payload, binding.name)),
+                });
+            }
+            if symbols.enums.contains_key(enum_name) { enum_name.clone() } else { "unknown".to_string() }
+        }
+        _ => get_type(pattern, symbols),
+    };
+    if found != scrutinee && found != "unknown" && scrutinee != "unknown" {
+        report_error(Diagnostic {
+            code: "E0308".to_string(),
+            message: "mismatched types".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("expected `{}`, found `{}`", scrutinee, found) },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some("each pattern must be a value of the matched type".to_string()),
+        });
+    }
+    Some(match pattern {
+        Node::Literal { value: LiteralValue::Bool(b), .. } => b.to_string(),
+        Node::MemberExpression { property, .. } if variants.is_some() => property.clone(),
+        Node::VariantPattern { variant, .. } if variants.is_some() => variant.clone(),
+        other => format!("{:?}", other),
+    })
+}
+
+/// Reports a pattern naming a variant that the matched enum does not have.
+fn check_variant_exists(enum_name: &str, variant: &str, p: &Pos, scrutinee: &str, variants: &Option<Vec<String>>) {
+    if enum_name != scrutinee || variants.iter().flatten().any(|v| v == variant) { return; }
+    report_error(Diagnostic {
+        code: "E0599".to_string(),
+        message: format!("no variant named `{}` found for enum `{}`", variant, enum_name),
+        primary_span: Span { line: p.line, column: p.column, length: enum_name.len() + variant.len() + 1, label: "variant not found".to_string() },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some(format!("`{}` has the variants {}", enum_name, quoted_list(variants.as_deref().unwrap_or_default()))),
+    });
+}
+
+/// `` `a` ``, `` `a` and `b` `` or `` `a`, `b` and `c` ``.
+fn quoted_list(names: &[String]) -> String {
+    let quoted: Vec<String> = names.iter().map(|n| format!("`{}`", n)).collect();
+    match quoted.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => quoted.concat(),
+    }
+}
+
+/// Checks the arms of a `match`: every pattern must be a value of the
+/// scrutinee's type, arms that can never be reached are warned about, and all
+/// variants must be covered unless there is a `_` arm.
+fn check_match(discriminant: &Node, arms: &[MatchArm], position: &Option<Pos>, symbols: &SymbolTable) {
+    let scrutinee = get_type(discriminant, symbols);
+    let variants = match_variants(&scrutinee, symbols);
+    let mut covered: Vec<String> = Vec::new();
+    let mut wildcard = false;
+    // Constant value and position of each integer arm so far.
+    let mut constants: Vec<(i64, Pos)> = Vec::new();
+    for arm in arms {
+        let value = match_pattern(&arm.pattern, &scrutinee, &variants, symbols);
+        if let Some(n) = eval_const(&arm.pattern, symbols).filter(|_| scrutinee == "int") {
+            let p = position_of(&arm.pattern).unwrap_or(Pos { line: 0, column: 0 });
+            if let Some((_, first)) = constants.iter().find(|(m, _)| *m == n) {
+                report_error(Diagnostic {
+                    code: "E0080".to_string(),
+                    message: format!("unreachable arm: duplicate value `{}`", n),
+                    primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("`{}` is already matched", n) },
+                    secondary_spans: vec![Span { line: first.line, column: first.column, length: 1, label: "first matched here".to_string() }],
+                    suggestion: None,
+                    note: Some("each arm of an integer `match` must match a different value".to_string()),
+                });
+            }
+            constants.push((n, p));
+        }
+        let all_covered = variants.as_ref().is_some_and(|all| all.iter().all(|v| covered.contains(v)));
+        let label = match &value {
+            _ if wildcard => Some("a `_` arm above already matches every value".to_string()),
+            Some(v) if covered.contains(v) => Some("this value is already matched above".to_string()),
+            _ if all_covered => Some(format!("every `{}` value is already matched above", scrutinee)),
+            _ => None,
+        };
+        if let Some(label) = label {
+            let p = position_of(&arm.pattern).unwrap_or(Pos { line: 0, column: 0 });
+            report_warning(Diagnostic {
+                code: "W0035".to_string(),
+                message: "unreachable pattern".to_string(),
+                primary_span: Span { line: p.line, column: p.column, length: 1, label },
+                secondary_spans: vec![],
+                suggestion: None,
+                note: Some("this arm never runs and can be removed".to_string()),
+            });
+        }
+        match value {
+            Some(v) => covered.push(v),
+            None => wildcard = true,
+        }
+    }
+    if wildcard || scrutinee == "unknown" { return; }
+    let missing: Vec<String> = match &variants {
+        Some(all) => all.iter().filter(|v| !covered.contains(v)).cloned().collect(),
+        None => vec!["_".to_string()],
+    };
+    if missing.is_empty() { return; }
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    let patterns = quoted_list(&missing);
+    report_error(Diagnostic {
+        code: "E0004".to_string(),
+        message: format!("non-exhaustive patterns: {} not covered", patterns),
+        primary_span: Span { line: p.line, column: p.column, length: 5, label: format!("pattern{} {} not covered", if missing.len() == 1 { "" } else { "s" }, patterns) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some(format!("the matched value has type `{}`; add an arm for {}, or a `_` arm", scrutinee, patterns)),
+    });
+}
+
+/// Warns about a `break` or `continue` that follows a `return` in the same statement list.
+fn check_control_after_return(stmts: &[Node]) {
+    let Some(ret) = stmts.iter().position(|s| matches!(s, Node::ReturnStatement { .. })) else { return };
+    let ret_pos = position_of(&stmts[ret]).unwrap_or(Pos { line: 0, column: 0 });
+    for stmt in &stmts[ret + 1..] {
+        let keyword = match stmt {
+            Node::BreakStatement { .. } => "break",
+            Node::ContinueStatement { .. } => "continue",
+            _ => continue,
+        };
+        let p = position_of(stmt).unwrap_or(Pos { line: 0, column: 0 });
+        report_warning(Diagnostic {
+            code: "W0029".to_string(),
+            message: "unreachable control-flow statement".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: keyword.len(), label: format!("this `{}` is never reached", keyword) },
+            secondary_spans: vec![Span { line: ret_pos.line, column: ret_pos.column, length: 6, label: "any code following this `return` is unreachable".to_string() }],
+            suggestion: None,
+            note: Some(format!("the function has already returned, so the `{}` has no effect on the loop", keyword)),
+        });
+    }
+}
+
+/// Warns when a user declaration uses the prefix reserved for compiler-generated names.
+fn check_reserved_name(name: &str, position: &Option<Pos>, symbols: &SymbolTable) {
+    if symbols.reserved_prefix.is_empty() || !name.starts_with(&symbols.reserved_prefix) { return; }
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    report_warning(Diagnostic {
+        code: "W0026".to_string(),
+        message: format!("`{}` uses a reserved prefix", name),
+        primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("names starting with `{}` are reserved for the compiler", symbols.reserved_prefix) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some("generated code may declare the same name; rename this declaration".to_string()),
+    });
+}
+
+/// Checks that a struct literal initializes every field, descending into nested
+/// struct literals so a missing field is reported by its path, e.g. `inner.x`.
+fn check_struct_literal(name: &str, inits: &[FieldInit], position: &Option<Pos>, path: &str, symbols: &mut SymbolTable) {
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    let Some(info) = symbols.structs.get(name) else {
+        report_error(Diagnostic {
+            code: "E0422".to_string(),
+            message: format!("cannot find struct `{}` in this scope", name),
+            primary_span: Span { line: p.line, column: p.column, length: name.len(), label: "not found in this scope".to_string() },
+            secondary_spans: vec![], suggestion: None, note: None,
+        });
+        return;
+    };
+    let positional = inits.iter().filter(|i| i.name.is_none()).count();
+    if positional > 0 && positional < inits.len() {
+        let (field, value) = inits.iter().find_map(|i| Some((i.name.as_ref()?, &i.value))).expect("some initializer is named");
+        let at = position_of(value).unwrap_or(p.clone());
+        report_error(Diagnostic {
+            code: "E0784".to_string(),
+            message: format!("initializer of `{}` mixes positional and named fields", name),
+            primary_span: Span { line: at.line, column: at.column, length: field.len(), label: format!("`{}` is named, but other fields are positional", field) },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some("use either `{ x: 1, y: 2 }` or `{ 1, 2 }` for the whole initializer".to_string()),
+        });
+    }
+    if positional > 0 && positional != info.fields.len() {
+        report_error(Diagnostic {
+            code: "E0061".to_string(),
+            message: format!("struct `{}` takes {} positional values, found {}", name, info.fields.len(), positional),
+            primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("expected {} values in field order", info.fields.len()) },
+            secondary_spans: vec![], suggestion: None, note: None,
+        });
+    }
+    let named: Vec<(String, &Node)> = if positional > 0 {
+        // Positional values initialize the fields in declaration order.
+        info.fields.iter().zip(inits).map(|(field, init)| (field.name.clone(), &init.value)).collect()
+    } else {
+        inits.iter().filter_map(|init| Some((init.name.clone()?, &init.value))).collect()
+    };
+    for (i, (field_name, value)) in named.iter().enumerate() {
+        let at = position_of(value).unwrap_or(p.clone());
+        if !info.fields.iter().any(|f| &f.name == field_name) {
+            report_error(Diagnostic {
+                code: "E0560".to_string(),
+                message: format!("struct `{}` has no field named `{}`", name, field_name),
+                primary_span: Span { line: at.line, column: at.column, length: 1, label: format!("unknown field `{}{}`", path, field_name) },
+                secondary_spans: vec![],
+                suggestion: None,
+                note: (!info.fields.is_empty()).then(|| format!("`{}` has the fields {}", name, quoted_list(&info.fields.iter().map(|f| f.name.clone()).collect::<Vec<_>>()))),
+            });
+        } else if named[..i].iter().any(|(n, _)| n == field_name) {
+            report_error(Diagnostic {
+                code: "E0062".to_string(),
+                message: format!("field `{}` specified more than once", field_name),
+                primary_span: Span { line: at.line, column: at.column, length: 1, label: "used more than once".to_string() },
+                secondary_spans: vec![], suggestion: None, note: None,
+            });
+        }
+    }
+    if let Some(missing) = info.fields.iter().find(|f| !named.iter().any(|(n, _)| n == &f.name)) {
+        report_error(Diagnostic {
+            code: "E0063".to_string(),
+            message: format!("missing field `{}` in initializer of `{}`", missing.name, name),
+            primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("missing field `{}{}`", path, missing.name) },
+            secondary_spans: vec![], suggestion: None, note: None,
+        });
+    }
+    let fields = struct_fields(&struct_literal_type(name, inits, symbols), symbols).unwrap_or_else(|| info.fields.clone());
+    for (field_name, value) in named {
+        match value {
+            Node::StructLiteral { name, fields, position } => {
+                check_struct_literal(name, fields, position, &format!("{}{}.", path, field_name), symbols);
+            }
+            value => check(value, symbols),
+        }
+        let Some(field) = fields.iter().find(|f| f.name == field_name) else { continue };
+        let found = get_type(value, symbols);
+        if found != "unknown" && !is_assignable(&field.field_type, &found) {
+            let at = position_of(value).unwrap_or(p.clone());
+            report_error(Diagnostic {
+                code: "E0308".to_string(),
+                message: "mismatched types".to_string(),
+                primary_span: Span { line: at.line, column: at.column, length: 1, label: format!("field `{}` expects `{}`, found `{}`", field_name, field.field_type, found) },
+                secondary_spans: vec![], suggestion: None, note: None,
+            });
+        }
+    }
+}
+
+/// Warns about top-level functions that `main` can never reach, even through
+/// other functions; a group of functions that only call each other is caught too.
+fn check_reachability(body: &[Node]) {
+    let program = serde_json::json!({ "type": "Program", "body": body });
+    let unreachable = deps::unreachable_functions(&program);
+    for stmt in body {
+        let Node::FunctionDeclaration { name, position, .. } = stmt else { continue };
+        if !unreachable.contains(name) { continue; }
+        let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+        report_warning(Diagnostic {
+            code: "W0031".to_string(),
+            message: format!("function `{}` is never reachable", name),
+            primary_span: Span { line: p.line, column: p.column, length: 2, label: "no call path from `main` leads to this function".to_string() },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some("remove the function, or call it from code that `main` reaches".to_string()),
+        });
+    }
+}
+
+/// Records `value` as a way of leaving the block labeled `name`, which must agree
+/// with every earlier value the block was left with.
+fn record_label_value(name: &str, value: &Node, symbols: &mut SymbolTable) {
+    let found = get_type(value, symbols);
+    if found == "unknown" { return; }
+    let Some(info) = symbols.labels.iter_mut().rev().find(|l| l.name == name) else { return };
+    match &info.value_type {
+        None => info.value_type = Some(found),
+        Some(expected) if *expected != found => {
+            let p = position_of(value).unwrap_or(Pos { line: 0, column: 0 });
+            report_error(Diagnostic {
+                code: "E0308".to_string(),
+                message: "mismatched types".to_string(),
+                primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("expected `{}`, found `{}`", expected, found) },
+                secondary_spans: vec![],
+                suggestion: None,
+                note: Some(format!("every value that leaves `'{}` must have the same type", name)),
+            });
+        }
+        Some(_) => {}
+    }
+}
+
+/// Kind and width of a numeric type: `(is_float, bits)`.
+fn numeric_kind(dtype: &str) -> Option<(bool, u32)> {
+    match dtype {
+        "i8" => Some((false, 8)),
+        "i16" => Some((false, 16)),
+        "i32" => Some((false, 32)),
+        "int" | "i64" => Some((false, 64)),
+        "f32" => Some((true, 32)),
+        "float" | "f64" => Some((true, 64)),
+        _ => None,
+    }
+}
+
+/// Whether casting a `from` value to `to` can lose data. `value` is the folded
+/// operand when it is a constant, in which case only that value has to fit.
+fn is_narrowing_cast(from: (bool, u32), to: (bool, u32), value: Option<i64>) -> bool {
+    match (from, to, value) {
+        ((true, _), (false, _), _) => true,
+        ((true, from_bits), (true, to_bits), _) => to_bits < from_bits,
+        ((false, _), (false, to_bits), Some(v)) => to_bits < 64 && (v < -(1i64 << (to_bits - 1)) || v >= 1i64 << (to_bits - 1)),
+        ((false, from_bits), (false, to_bits), None) => to_bits < from_bits,
+        // Integers convert to floats exactly up to the float's mantissa width.
+        ((false, _), (true, to_bits), Some(v)) => v.unsigned_abs() > 1u64 << if to_bits == 32 { 24 } else { 53 },
+        ((false, _), (true, _), None) => false,
+    }
+}
+
+fn check_cast(expression: &Node, target_type: &str, position: &Option<Pos>, symbols: &SymbolTable) {
+    let source_type = get_type(expression, symbols);
+    if source_type == "unknown" || source_type == target_type { return; }
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    let span = |label: String| Span { line: p.line, column: p.column, length: 1, label };
+    match (numeric_kind(&source_type), numeric_kind(target_type)) {
+        (Some(from), Some(to)) => {
+            if is_narrowing_cast(from, to, eval_const(expression, symbols)) {
+                report_warning(Diagnostic {
+                    code: "W0024".to_string(),
+                    message: "cast may lose data".to_string(),
+                    primary_span: span(format!("`{}` does not fit every `{}` value", target_type, source_type)),
+                    secondary_spans: vec![], suggestion: None, note: None,
+                });
+            }
+        }
+        (None, Some((false, _))) if source_type == "bool" => {}
+        _ => report_error(Diagnostic {
+            code: "E0605".to_string(),
+            message: format!("non-primitive cast: `{}` as `{}`", source_type, target_type),
+            primary_span: span("an `as` expression can only be used to convert between numeric types".to_string()),
+            secondary_spans: vec![], suggestion: None, note: None,
+        }),
+    }
+}
+
+/// Warns when an assignment is used where a `bool` is expected, which is almost always a mistyped `==`.
+fn check_bool_context(node: &Node) {
+    if let Node::AssignmentExpression { position, .. } = node {
+        let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+        report_warning(Diagnostic {
+            code: "W0020".to_string(),
+            message: "assignment used as a boolean value".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 1, label: "this assigns instead of comparing".to_string() },
+            secondary_spans: vec![],
+            suggestion: Some(Suggestion { message: "use `==` to compare the values".to_string(), replacement: "==".to_string() }),
+            note: None,
+        });
+    }
+}
+
+/// Warns about a function with more parameters than `--max-params` allows.
+fn check_param_count(name: &str, params: &[Param], position: &Option<Pos>, symbols: &SymbolTable) {
+    if params.len() <= symbols.max_params { return; }
+    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+    report_warning(Diagnostic {
+        code: "W0033".to_string(),
+        message: format!("function `{}` has too many parameters", name),
+        primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("{} parameters, more than the limit of {}", params.len(), symbols.max_params) },
+        secondary_spans: vec![],
+        suggestion: None,
+        note: Some("group related parameters into a struct".to_string()),
+    });
+}
+
 fn check(node: &Node, symbols: &mut SymbolTable) {
     match node {
         Node::Program { body } => {
             for stmt in body {
                 match stmt {
-                    Node::FunctionDeclaration { name, params, return_type, .. } => {
+                    Node::FunctionDeclaration { name, params, return_type, position, .. } => {
+                        check_reserved_name(name, position, symbols);
+                        check_param_count(name, params, position, symbols);
                         let p_types = params.iter().map(|p| p.param_type.clone()).collect();
                         symbols.functions.insert(name.clone(), (p_types, return_type.clone()));
                     }
-                    Node::StructDeclaration { name, fields, .. } => {
-                        let mut field_map = HashMap::new();
-                        for f in fields { field_map.insert(f.name.clone(), f.field_type.clone()); }
-                        symbols.structs.insert(name.clone(), StructInfo { fields: field_map });
+                    Node::StructDeclaration { name, type_params, fields, methods, position } => {
+                        check_reserved_name(name, position, symbols);
+                        let methods = methods.iter().filter_map(method_signature).collect();
+                        symbols.structs.insert(name.clone(), StructInfo { type_params: type_params.clone(), fields: fields.clone(), methods });
+                    }
+                    Node::EnumDeclaration { name, variants, attributes, position } => {
+                        check_reserved_name(name, position, symbols);
+                        symbols.enums.insert(name.clone(), variants.iter().map(|v| v.name.clone()).collect());
+                        if attributes.iter().any(|a| a == "bitflags") { symbols.bitflags.insert(name.clone()); }
+                        for v in variants {
+                            if let Some(payload) = &v.payload { symbols.payloads.insert(format!("{}.{}", name, v.name), payload.clone()); }
+                        }
                     }
                     _ => {}
                 }
             }
+            // Impl blocks can come before the struct they extend, so merge them once all structs are known.
+            for stmt in body {
+                if let Node::ImplBlock { type_name, methods, position } = stmt {
+                    register_impl_methods(type_name, methods, position, symbols);
+                }
+            }
             for stmt in body { check(stmt, symbols); }
+            check_reachability(body);
         }
-        Node::FunctionDeclaration { params, body, .. } => {
+        Node::FunctionDeclaration { name, params, return_type, body, position } => {
+            let fn_pos = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+            // Top-level functions are registered up front; a nested one becomes callable from here on.
+            if symbols.current_impl.is_none() && !symbols.functions.contains_key(name) {
+                symbols.functions.insert(name.clone(), (params.iter().map(|p| p.param_type.clone()).collect(), return_type.clone()));
+                symbols.forget_types();
+            }
+            let outer_returns = std::mem::take(&mut symbols.returns);
+            let outer_return_type = std::mem::replace(&mut symbols.return_type, return_type.clone());
             symbols.enter_scope();
-            for p in params { symbols.define(p.name.clone(), p.param_type.clone()); }
-            check(body, symbols);
+            for p in params {
+                check_reserved_name(&p.name, position, symbols);
+                check_type_annotation(&p.param_type, &fn_pos, symbols);
+                let param_type = match (&symbols.current_impl, p.param_type.as_str()) {
+                    (Some(impl_type), "self") => impl_type.clone(),
+                    _ => p.param_type.clone(),
+                };
+                symbols.define(p.name.clone(), param_type, position.clone());
+            }
+            if let Node::BlockStatement { body: stmts, value: Some(tail), .. } = &**body {
+                // The tail value can refer to the block's locals, so type it before the block scope closes.
+                check_control_after_return(stmts);
+                symbols.enter_scope();
+                for stmt in stmts { check(stmt, symbols); }
+                check(tail, symbols);
+                let tail_type = get_type(tail, symbols);
+                symbols.returns.push((tail_type, position_of(tail).unwrap_or(Pos { line: 0, column: 0 })));
+                symbols.exit_scope();
+            } else {
+                check(body, symbols);
+            }
             symbols.exit_scope();
+            let returns = std::mem::replace(&mut symbols.returns, outer_returns);
+            symbols.return_type = outer_return_type;
+            let resolved = unify_returns(return_type, &returns);
+            if return_type == "auto" && resolved == "unknown" && !returns.is_empty() {
+                check_recursive_inference(name, body, &fn_pos);
+            }
+            if return_type == "auto" { record_inferred_return(name, resolved, symbols); }
         }
-        Node::VariableDeclaration { identifier, data_type, initializer, position, .. } => {
+        Node::ReturnStatement { argument, position } => {
+            let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+            let value_type = match argument {
+                Some(arg) => {
+                    check(arg, symbols);
+                    get_type(arg, symbols)
+                }
+                None if matches!(symbols.return_type.as_str(), "" | "void" | "auto") => "void".to_string(),
+                None => {
+                    report_error(Diagnostic {
+                        code: "E0069".to_string(),
+                        message: "`return;` in a function whose return type is not `void`".to_string(),
+                        primary_span: Span { line: p.line, column: p.column, length: 6, label: format!("return type is `{}`", symbols.return_type) },
+                        secondary_spans: vec![],
+                        suggestion: None,
+                        note: Some(format!("return a value of type `{}`", symbols.return_type)),
+                    });
+                    "unknown".to_string()
+                }
+            };
+            symbols.returns.push((value_type, argument.as_deref().and_then(position_of).unwrap_or(p)));
+        }
+        Node::VariableDeclaration { identifier, data_type, is_constant, mutable, initializer, position } => {
+            let decl_pos = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+            check_reserved_name(identifier, position, symbols);
+            check_loop_var_shadowing(identifier, &decl_pos, symbols);
+            check_type_annotation(data_type, &decl_pos, symbols);
+            if is_constant.unwrap_or(false) && initializer.is_none() {
+                report_error(Diagnostic {
+                    code: "E0317".to_string(),
+                    message: format!("`const` `{}` must be initialized", identifier),
+                    primary_span: Span { line: decl_pos.line, column: decl_pos.column, length: identifier.len(), label: "constant declared without a value".to_string() },
+                    secondary_spans: vec![],
+                    suggestion: None,
+                    note: Some(format!("a `const` cannot be assigned later; give it a value, e.g. `const {}: {} = ...;`", identifier, data_type)),
+                });
+            } else if data_type == "auto" && initializer.is_none() {
+                report_error(Diagnostic {
+                    code: "E0282".to_string(),
+                    message: "type annotations needed".to_string(),
+                    primary_span: Span { line: decl_pos.line, column: decl_pos.column, length: identifier.len(), label: format!("cannot infer the type of `{}`", identifier) },
+                    secondary_spans: vec![],
+                    suggestion: None,
+                    note: Some(format!("without a value the type cannot be inferred; write `let {}: T;` with a type", identifier)),
+                });
+            }
             if let Some(init) = initializer {
+                check(init, symbols);
+                if data_type == "bool" { check_bool_context(init); }
+                check_not_void(init, symbols);
                 let init_type = get_type(init, symbols);
-                if data_type != "auto" && init_type != "unknown" && data_type != &init_type {
+                check_array_length(data_type, &init_type, identifier, &decl_pos, symbols);
+                if data_type != "auto" && init_type != "unknown" && !is_assignable(data_type, &init_type) {
                     let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
                     report_error(Diagnostic {
                         code: "E0308".to_string(), message: "mismatched types".to_string(),
                         primary_span: Span { line: p.line, column: p.column, length: identifier.len(), label: format!("expected `{}`, found `{}`", data_type, init_type) },
-                        secondary_spans: vec![], suggestion: None, note: None,
+                        secondary_spans: vec![], suggestion: None, note: unwrap_note(data_type, &init_type),
                     });
                 }
             }
-            symbols.define(identifier.clone(), data_type.clone());
+            let mut const_value = None;
+            if is_constant.unwrap_or(false) {
+                if let Some(init) = initializer {
+                    const_value = eval_const(init, symbols);
+                    if const_value.is_none() && data_type == "int" {
+                        report_error(Diagnostic {
+                            code: "E0435".to_string(),
+                            message: "attempt to use a non-constant value in a constant".to_string(),
+                            primary_span: Span { line: decl_pos.line, column: decl_pos.column, length: identifier.len(), label: format!("initializer of `{}` is not a constant expression", identifier) },
+                            secondary_spans: vec![], suggestion: None, note: None,
+                        });
+                    }
+                }
+            }
+            // `let x = ...` takes the initializer's type, so conditions and calls can see it.
+            let dtype = match (data_type.as_str(), initializer) {
+                ("auto", Some(init)) => get_type(init, symbols),
+                ("auto", None) => "unknown".to_string(),
+                _ => data_type.clone(),
+            };
+            symbols.define_const(identifier.clone(), dtype, const_value, position.clone());
+            // A `let` declared without a value is assigned once later, which the analyzer tracks.
+            if is_constant.unwrap_or(false) || (*mutable == Some(false) && initializer.is_some()) {
+                symbols.set_immutable(identifier);
+            }
         }
         Node::AssignmentExpression { left, right, position } => {
             let var_type = get_type(left, symbols);
             let val_type = get_type(right, symbols);
-            if var_type != "unknown" && val_type != "unknown" && var_type != val_type {
-                let name = match &**left {
+            let name = match &**left {
+                Node::Identifier { name, .. } => name.clone(),
+                Node::MemberExpression { property, .. } => property.clone(),
+                Node::UnaryExpression { argument, .. } => match &**argument {
+                    Node::Identifier { name, .. } => format!("*{}", name),
+                    _ => "expression".to_string(),
+                },
+                Node::IndexExpression { object, .. } => match &**object {
                     Node::Identifier { name, .. } => name.clone(),
-                    Node::MemberExpression { property, .. } => property.clone(),
                     _ => "expression".to_string(),
-                };
-                let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+                },
+                _ => "expression".to_string(),
+            };
+            let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+            if let Node::Identifier { name, .. } = &**left { check_assignable_binding(name, &p, symbols); }
+            check_array_length(&var_type, &val_type, &name, &p, symbols);
+            if var_type != "unknown" && val_type != "unknown" && !is_assignable(&var_type, &val_type) {
                 report_error(Diagnostic {
                     code: "E0308".to_string(),
                     message: "mismatched types during assignment".to_string(),
@@ -198,15 +1733,37 @@ fn check(node: &Node, symbols: &mut SymbolTable) {
                         line: p.line, column: p.column, length: name.len(),
                         label: format!("expected `{}`, found `{}`", var_type, val_type),
                     },
-                    secondary_spans: vec![], suggestion: None, note: None,
+                    secondary_spans: vec![], suggestion: None, note: unwrap_note(&var_type, &val_type),
                 });
             }
             check(left, symbols);
             check(right, symbols);
         }
         Node::CallExpression { callee, arguments, position } => {
+            if let Node::MemberExpression { object, .. } = &**callee { check(object, symbols); }
+            for arg in arguments { check(arg, symbols); }
             if let Node::Identifier { name, .. } = &**callee {
-                if name == "println" { return; }
+                if name == "__assert_type" {
+                    check_type_assertion(arguments, position, symbols);
+                    return;
+                }
+                if name == "println" {
+                    check_format_string(arguments);
+                    for arg in arguments {
+                        let arg_type = get_type(arg, symbols);
+                        if !matches!(arg_type.as_str(), "int" | "float" | "bool" | "string" | "unknown") {
+                            let p = position_of(arg).or(position.clone()).unwrap_or(Pos { line: 0, column: 0 });
+                            report_error(Diagnostic {
+                                code: "E0277".to_string(),
+                                message: format!("`{}` cannot be printed", arg_type),
+                                primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("`{}` is not a printable type", arg_type) },
+                                secondary_spans: vec![], suggestion: None,
+                                note: Some("`println` accepts `int`, `float`, `bool` and `string` values".to_string()),
+                            });
+                        }
+                    }
+                    return;
+                }
                 if let Some((p_types, _)) = symbols.functions.get(name).cloned() {
                     if p_types.len() != arguments.len() {
                         let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
@@ -217,71 +1774,416 @@ fn check(node: &Node, symbols: &mut SymbolTable) {
                             secondary_spans: vec![], suggestion: None, note: None,
                         });
                     }
-                    for (i, arg) in arguments.iter().enumerate() {
+                    for (i, (param_type, arg)) in p_types.iter().zip(arguments).enumerate() {
+                        let mut expected = param_type.as_str();
+                        if let Some(inner) = expected.strip_prefix("ref<").and_then(|t| t.strip_suffix('>')) {
+                            if !is_place(arg) {
+                                let p = position_of(arg).or(position.clone()).unwrap_or(Pos { line: 0, column: 0 });
+                                report_error(Diagnostic {
+                                    code: "E0716".to_string(),
+                                    message: "temporary value borrowed".to_string(),
+                                    primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("argument #{} is a temporary, but `{}` expects a `{}`", i + 1, name, expected) },
+                                    secondary_spans: vec![],
+                                    suggestion: None,
+                                    note: Some("store the value in a variable and pass that instead".to_string()),
+                                });
+                            }
+                            // A `ref<T>` parameter borrows a `T` place.
+                            expected = inner;
+                        }
                         let arg_type = get_type(arg, symbols);
-                        if arg_type != "unknown" && arg_type != p_types[i] {
+                        if arg_type != "unknown" && !is_assignable(param_type, &arg_type) && !is_assignable(expected, &arg_type) {
                             let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
                             report_error(Diagnostic {
                                 code: "E0308".to_string(),
                                 message: format!("argument type mismatch in call to `{}`", name),
-                                primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("argument #{} expected `{}`, found `{}`", i+1, p_types[i], arg_type) },
-                                secondary_spans: vec![], suggestion: None, note: None,
+                                primary_span: Span { line: p.line, column: p.column, length: name.len(), label: format!("argument #{} expected `{}`, found `{}`", i+1, expected, arg_type) },
+                                secondary_spans: vec![], suggestion: None, note: null_argument_note(expected, &arg_type),
                             });
                         }
                     }
+                } else {
+                    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+                    report_error(Diagnostic {
+                        code: "E0425".to_string(),
+                        message: format!("cannot find function `{}` in this scope", name),
+                        primary_span: Span { line: p.line, column: p.column, length: name.len(), label: "not found in this scope".to_string() },
+                        secondary_spans: vec![],
+                        suggestion: None,
+                        note: None,
+                    });
                 }
             }
         }
-        Node::BinaryExpression { operator, left, right, position } => {
+        Node::BinaryExpression { operator, left, right, position, .. } if operator == "??" => {
+            check_not_void(left, symbols);
+            check_not_void(right, symbols);
+            check_null_coalesce(left, right, position, symbols);
+            check(left, symbols);
+            check(right, symbols);
+        }
+        Node::BinaryExpression { operator, left, right, position, .. } => {
+            check_precedence(operator, left, right, position);
+            check_enum_operator(operator, left, right, position, symbols);
+            if operator == "&&" || operator == "||" {
+                check_bool_context(left);
+                check_bool_context(right);
+            }
+            check_not_void(left, symbols);
+            check_not_void(right, symbols);
             let lt = get_type(left, symbols);
             let rt = get_type(right, symbols);
-            if lt != "unknown" && rt != "unknown" && lt != rt {
-                if (lt == "string" && rt != "string") || (rt == "string" && lt != "string") {
-                    let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
-                    report_error(Diagnostic {
-                        code: "E0308".to_string(),
-                        message: "operator type mismatch".to_string(),
-                        primary_span: Span { line: p.line, column: p.column, length: operator.len(), label: format!("cannot apply `{}` to `{}` and `{}`", operator, lt, rt) },
-                        secondary_spans: vec![], suggestion: None, note: None,
-                    });
-                }
+            if lt != "unknown" && rt != "unknown" && lt != rt
+                && ((lt == "string" && rt != "string") || (rt == "string" && lt != "string")) {
+                let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+                report_error(Diagnostic {
+                    code: "E0308".to_string(),
+                    message: "operator type mismatch".to_string(),
+                    primary_span: Span { line: p.line, column: p.column, length: operator.len(), label: format!("cannot apply `{}` to `{}` and `{}`", operator, lt, rt) },
+                    secondary_spans: vec![], suggestion: None, note: None,
+                });
             }
             check(left, symbols);
             check(right, symbols);
         }
-        Node::BlockStatement { body, .. } => {
+        Node::BlockStatement { body, value, label, .. } => {
+            if let Some(name) = label {
+                symbols.labels.push(LabelInfo { name: name.clone(), value_type: None });
+            }
+            check_control_after_return(body);
             symbols.enter_scope();
             for stmt in body { check(stmt, symbols); }
+            if let Some(v) = value {
+                check(v, symbols);
+                if let Some(name) = label { record_label_value(name, v, symbols); }
+            }
             symbols.exit_scope();
+            if let Some(name) = label {
+                let info = symbols.labels.pop().expect("label pushed above");
+                symbols.label_types.insert(name.clone(), info.value_type.unwrap_or_else(|| "void".to_string()));
+                symbols.forget_types();
+            }
+        }
+        Node::BreakStatement { label: Some(name), argument, position } => {
+            if !symbols.labels.iter().any(|l| &l.name == name) {
+                let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+                report_error(Diagnostic {
+                    code: "E0426".to_string(),
+                    message: format!("use of undeclared label `'{}`", name),
+                    primary_span: Span { line: p.line, column: p.column, length: name.len() + 7, label: format!("undeclared label `'{}`", name) },
+                    secondary_spans: vec![], suggestion: None, note: None,
+                });
+            }
+            if let Some(arg) = argument {
+                check(arg, symbols);
+                record_label_value(name, arg, symbols);
+            }
         }
         Node::ExpressionStatement { expression } => check(expression, symbols),
+        Node::Identifier { name, position } if name == "self" && (symbols.current_impl.is_none() || symbols.get(name).is_none()) => {
+            let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+            let note = match &symbols.current_impl {
+                Some(type_name) => format!("add a `self` parameter to this method of `{}` to use it", type_name),
+                None => "`self` is only available inside the methods of an `impl` block".to_string(),
+            };
+            report_error(Diagnostic {
+                code: "E0424".to_string(),
+                message: "`self` is not available in this context".to_string(),
+                primary_span: Span { line: p.line, column: p.column, length: 4, label: "`self` used outside of a method".to_string() },
+                secondary_spans: vec![],
+                suggestion: None,
+                note: Some(note),
+            });
+        }
+        Node::Identifier { name, position } if symbols.get(name).is_none() => {
+            // Functions, types and builtins are names too, just not values in a scope.
+            let is_item = name == "println" || symbols.functions.contains_key(name)
+                || symbols.structs.contains_key(name) || symbols.enums.contains_key(name);
+            if is_item { return; }
+            let p = position.clone().unwrap_or(Pos { line: 0, column: 0 });
+            let (secondary_spans, note) = match symbols.ended.get(name) {
+                Some(declared) => (
+                    vec![Span { line: declared.line, column: declared.column, length: name.len(), label: format!("`{}` is declared here, in a block that has ended", name) }],
+                    Some(format!("`{}` is only visible inside the block that declares it", name)),
+                ),
+                None => (vec![], Some(format!("declare `{}` with `let` before using it", name))),
+            };
+            report_error(Diagnostic {
+                code: "E0425".to_string(),
+                message: format!("cannot find value `{}` in this scope", name),
+                primary_span: Span { line: p.line, column: p.column, length: name.len(), label: "not found in this scope".to_string() },
+                secondary_spans,
+                suggestion: None,
+                note,
+            });
+        }
+        Node::IndexExpression { object, index, position } => {
+            check(object, symbols);
+            check(index, symbols);
+            check_index_type(object, index, position, symbols);
+        }
+        Node::MemberExpression { object, .. } => check(object, symbols),
+        Node::ArrayLiteral { elements, .. } => {
+            for element in elements { check(element, symbols); }
+            check_array_elements(elements, symbols);
+        }
+        Node::TupleLiteral { elements, .. } => {
+            for element in elements { check(element, symbols); }
+        }
+        Node::TupleDeclaration { names, initializer, position, .. } => {
+            check(initializer, symbols);
+            check_tuple_declaration(names, initializer, position, symbols);
+        }
+        Node::StructDeclaration { name, fields, methods, position, .. } if fields.is_empty() && methods.is_empty() => {
+            warn_empty_declaration("struct", name, position);
+        }
+        Node::EnumDeclaration { name, variants, position, .. } => {
+            if variants.is_empty() { warn_empty_declaration("enum", name, position); }
+            check_discriminants(name, variants, position, symbols);
+        }
+        Node::ImplBlock { type_name, methods, .. } => {
+            symbols.current_impl = Some(type_name.clone());
+            for method in methods { check(method, symbols); }
+            symbols.current_impl = None;
+        }
+        Node::StructLiteral { name, fields, position } => check_struct_literal(name, fields, position, "", symbols),
+        Node::CastExpression { expression, target_type, position } => {
+            check(expression, symbols);
+            check_not_void(expression, symbols);
+            check_cast(expression, target_type, position, symbols);
+        }
+        Node::UnaryExpression { operator, argument } => {
+            if operator == "!" { check_bool_context(argument); }
+            check_not_void(argument, symbols);
+            check(argument, symbols);
+        }
+        Node::IfStatement { test, consequent, alternate, binding: Some(binding), position } => {
+            check(test, symbols);
+            check_let_binding(binding, test, consequent, position, symbols);
+            if let Some(alt) = alternate { check(alt, symbols); }
+        }
         Node::IfStatement { test, consequent, alternate, .. } => {
+            check_bool_context(test);
             check(test, symbols);
-            check(consequent, symbols);
+            check_condition_type(test, symbols);
+            if let Some(alt) = alternate { check_redundant_else(consequent, alt); }
+            // After `if (o != null)`, an `optional<T>` variable holds a `T` in the consequent.
+            let narrowed = null_checked(test).and_then(|name| {
+                let inner = optional_inner(&symbols.lookup(name)?)?.to_string();
+                Some((name.to_string(), inner))
+            });
+            if let Some((name, inner)) = narrowed {
+                symbols.enter_scope();
+                symbols.define(name, inner, None);
+                check(consequent, symbols);
+                symbols.exit_scope();
+            } else {
+                check(consequent, symbols);
+            }
             if let Some(alt) = alternate { check(alt, symbols); }
         }
+        Node::WhileStatement { test, body, binding: Some(binding), position } => {
+            check(test, symbols);
+            check_let_binding(binding, test, body, position, symbols);
+        }
         Node::WhileStatement { test, body, .. } => {
+            check_bool_context(test);
             check(test, symbols);
+            check_condition_type(test, symbols);
             check(body, symbols);
         }
         Node::ForStatement { init: f_init, test: f_test, update: f_update, body, .. } => {
             symbols.enter_scope();
-            if let Some(ref i) = f_init { check(&*i, symbols); }
-            if let Some(ref t) = f_test { check(&*t, symbols); }
-            if let Some(ref u) = f_update { check(&*u, symbols); }
+            if let Some(i) = f_init { check(i, symbols); }
+            if let Some(t) = f_test {
+                check_bool_context(t);
+                check(t, symbols);
+                check_condition_type(t, symbols);
+            }
+            if let Some(u) = f_update { check(u, symbols); }
+            let induction = match f_init.as_deref() {
+                Some(Node::VariableDeclaration { identifier, position: Some(p), .. }) => Some((identifier.clone(), p.clone())),
+                _ => None,
+            };
+            let is_induction = induction.is_some();
+            symbols.loop_vars.extend(induction);
             check(body, symbols);
+            if is_induction { symbols.loop_vars.pop(); }
             symbols.exit_scope();
         }
+        Node::MatchStatement { discriminant, arms, position } => {
+            check(discriminant, symbols);
+            check_match(discriminant, arms, position, symbols);
+            for arm in arms {
+                symbols.enter_scope();
+                if let Node::VariantPattern { enum_name, variant, binding, position } = &arm.pattern {
+                    let payload = symbols.payloads.get(&format!("{}.{}", enum_name, variant)).cloned();
+                    let dtype = if binding.param_type == "auto" { payload.unwrap_or_else(|| "unknown".to_string()) } else { binding.param_type.clone() };
+                    symbols.define(binding.name.clone(), dtype, position.clone());
+                }
+                check(&arm.body, symbols);
+                symbols.exit_scope();
+            }
+        }
         _ => {}
     }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 { return; }
-    let input = fs::read_to_string(&args[1]).expect("Failed to read AST");
+    let mut args = env::args().skip(1);
+    let (mut path, mut format, mut source, mut reserved_prefix, mut emit) = (None, None, None, None, None);
+    let (mut check_overflow, mut type_assertions) = (false, false);
+    let mut diagnostic_format = None;
+    let mut max_params = None;
+    let mut allowed: HashSet<String> = env::var("FAX_ALLOW").unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|code| !code.is_empty())
+        .map(str::to_uppercase)
+        .collect();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow" => allowed.extend(args.next().map(|code| code.to_uppercase())),
+            "--format" => format = args.next(),
+            "--diagnostic-format" => diagnostic_format = args.next(),
+            "--source" => source = args.next(),
+            "--reserved-prefix" => reserved_prefix = args.next(),
+            "--emit" => emit = args.next(),
+            "--check-overflow" => check_overflow = true,
+            "--type-assertions" => type_assertions = true,
+            "--max-params" => {
+                let value = args.next().unwrap_or_default();
+                match value.parse::<usize>() {
+                    Ok(n) => max_params = Some(n),
+                    Err(_) => {
+                        eprintln!("invalid value `{}` for `--max-params` (expected a number)", value);
+                        std::process::exit(2);
+                    }
+                }
+            }
+            "--explain" => {
+                let code = args.next().unwrap_or_default();
+                match explain::explain(&code) {
+                    Some(text) => println!("{}: {}", code.to_uppercase(), text),
+                    None => {
+                        eprintln!("error: no extended information for `{}`", code);
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else { return };
+    ALLOWED.set(allowed).unwrap();
+    match format.as_deref() {
+        None | Some("json") => {}
+        Some("sarif") => { SARIF_URI.set(source.unwrap_or_else(|| path.clone())).unwrap(); }
+        Some(other) => {
+            eprintln!("unknown diagnostic format `{}` (expected `json` or `sarif`)", other);
+            std::process::exit(2);
+        }
+    }
+    let ndjson = match diagnostic_format.as_deref() {
+        None | Some("array") => false,
+        Some("ndjson") => true,
+        Some(other) => {
+            eprintln!("unknown diagnostic framing `{}` (expected `array` or `ndjson`)", other);
+            std::process::exit(2);
+        }
+    };
+    match emit.as_deref() {
+        None | Some("ast") => {}
+        Some("deps") if SARIF_URI.get().is_some() => {
+            eprintln!("`--emit deps` cannot be combined with `--format sarif`; both are written to stdout");
+            std::process::exit(2);
+        }
+        Some("deps") => {}
+        Some(other) => {
+            eprintln!("unknown emit kind `{}` (expected `ast` or `deps`)", other);
+            std::process::exit(2);
+        }
+    }
+    let input = fs::read_to_string(&path).expect("Failed to read AST");
     let ast: Node = serde_json::from_str(&input).expect("Failed to parse AST JSON");
     let mut symbols = SymbolTable::new();
+    if let Some(prefix) = reserved_prefix { symbols.reserved_prefix = prefix; }
+    symbols.type_assertions = type_assertions;
+    if let Some(n) = max_params { symbols.max_params = n; }
     check(&ast, &mut symbols);
-    println!("{}", input);
+    if check_overflow {
+        for diag in ranges::check_overflow(&ast) { report_warning(diag); }
+    }
+    if SARIF_URI.get().is_some() {
+        emit_sarif();
+        if has_errors() { std::process::exit(1); }
+        return;
+    }
+    emit_diagnostics(ndjson);
+    if has_errors() { std::process::exit(1); }
+    if emit.as_deref() == Some("deps") {
+        let program: serde_json::Value = serde_json::from_str(&input).expect("Failed to parse AST JSON");
+        println!("{}", serde_json::to_string_pretty(&deps::dependency_graph(&program)).unwrap());
+    } else {
+        println!("{}", input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_of(node: &Node) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        node.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_structurally_identical_asts_are_equal() {
+        let a: Node = serde_json::from_str(r#"{"type": "BinaryExpression", "operator": "*",
+            "left": {"type": "Literal", "value": 2.5, "position": {"line": 1, "column": 9}},
+            "right": {"type": "Identifier", "name": "n", "position": {"line": 1, "column": 15}},
+            "position": {"line": 1, "column": 9}}"#).unwrap();
+        let b: Node = serde_json::from_str(r#"{
+            "position": {"column": 9, "line": 1},
+            "right": {"name": "n", "type": "Identifier", "position": {"line": 1, "column": 15}},
+            "left": {"value": 2.5, "type": "Literal", "position": {"line": 1, "column": 9}},
+            "operator": "*", "type": "BinaryExpression"
+        }"#).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+
+        let c: Node = serde_json::from_str(&serde_json::to_string(&a).unwrap().replace("2.5", "2.6")).unwrap();
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_each_node_type_is_computed_once() {
+        // `x + x + ... + x`, nested to the left: checking it used to retype every
+        // subtree once per enclosing `+`, quadratic in the depth.
+        let depth = 50;
+        let mut expr = serde_json::json!({ "type": "Identifier", "name": "x" });
+        for _ in 0..depth {
+            expr = serde_json::json!({ "type": "BinaryExpression", "operator": "+", "left": expr, "right": { "type": "Identifier", "name": "x" } });
+        }
+        let expr: Node = serde_json::from_value(expr).unwrap();
+        let mut symbols = SymbolTable::new();
+        symbols.define("x".to_string(), "int".to_string(), None);
+        check(&expr, &mut symbols);
+        assert_eq!(get_type(&expr, &symbols), "int");
+        assert_eq!(symbols.types_computed.get(), 2 * depth + 1);
+    }
+
+    #[test]
+    fn test_eval_const_folds_decoded_hex_literal() {
+        // `0xFF + 1` as the parser emits it: the lexer's hex text is already decoded to 255.
+        let expr: Node = serde_json::from_str(r#"{
+            "type": "BinaryExpression", "operator": "+",
+            "left": { "type": "Literal", "value": 255 },
+            "right": { "type": "Literal", "value": 1 }
+        }"#).unwrap();
+        assert_eq!(eval_const(&expr, &SymbolTable::new()), Some(256));
+    }
 }
@@ -0,0 +1,232 @@
+//! Opt-in integer overflow detection for `--check-overflow`: tracks constant
+//! bounds of `int` variables through straight-line code and counted `for`
+//! loops, and reports arithmetic whose bounds leave the `i64` range.
+
+use crate::{Diagnostic, LiteralValue, Node, Pos, Span};
+use std::collections::HashMap;
+
+/// Inclusive bounds of an `int` value. Kept in `i128` so overflowing `i64` results can be seen.
+#[derive(Clone, Copy, PartialEq)]
+struct Range { lo: i128, hi: i128 }
+
+impl Range {
+    fn exact(n: i128) -> Self { Range { lo: n, hi: n } }
+    fn fits(&self) -> bool { self.lo >= i64::MIN as i128 && self.hi <= i64::MAX as i128 }
+    fn hull(self, other: Range) -> Self { Range { lo: self.lo.min(other.lo), hi: self.hi.max(other.hi) } }
+}
+
+/// Bounds of the variables known so far; a variable missing here is unbounded.
+type Env = HashMap<String, Range>;
+
+#[derive(Default)]
+struct Analysis { warnings: Vec<Diagnostic> }
+
+impl Analysis {
+    fn warn(&mut self, pos: &Option<Pos>, range: Range) {
+        let p = pos.clone().unwrap_or(Pos { line: 0, column: 0 });
+        let bound = if range.hi > i64::MAX as i128 { range.hi } else { range.lo };
+        self.warnings.push(Diagnostic {
+            code: "W0032".to_string(),
+            message: "this arithmetic may overflow `int`".to_string(),
+            primary_span: Span { line: p.line, column: p.column, length: 1, label: format!("the result can reach {}", bound) },
+            secondary_spans: vec![],
+            suggestion: None,
+            note: Some(format!("`int` holds values from {} to {}", i64::MIN, i64::MAX)),
+        });
+    }
+
+    /// Bounds of `node`, or `None` when they are unknown. An operation whose
+    /// bounds overflow is reported once and then treated as unknown.
+    fn eval(&mut self, node: &Node, env: &Env) -> Option<Range> {
+        match node {
+            Node::Literal { value: LiteralValue::Int(n), .. } => Some(Range::exact(*n as i128)),
+            Node::Identifier { name, .. } => env.get(name).copied(),
+            Node::UnaryExpression { operator, argument } if operator == "-" => {
+                let r = self.eval(argument, env)?;
+                Some(Range { lo: -r.hi, hi: -r.lo })
+            }
+            Node::BinaryExpression { operator, left, right, position, .. } => {
+                let (l, r) = (self.eval(left, env), self.eval(right, env));
+                let (l, r) = (l?, r?);
+                let result = match operator.as_str() {
+                    "+" => Range { lo: l.lo + r.lo, hi: l.hi + r.hi },
+                    "-" => Range { lo: l.lo - r.hi, hi: l.hi - r.lo },
+                    "*" => {
+                        let products = [l.lo.checked_mul(r.lo), l.lo.checked_mul(r.hi), l.hi.checked_mul(r.lo), l.hi.checked_mul(r.hi)];
+                        let products: Option<Vec<i128>> = products.into_iter().collect();
+                        let products = products?;
+                        Range { lo: *products.iter().min()?, hi: *products.iter().max()? }
+                    }
+                    _ => return None,
+                };
+                if result.fits() { return Some(result); }
+                self.warn(position, result);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn block(&mut self, stmts: &[Node], env: &mut Env) {
+        for stmt in stmts { self.statement(stmt, env); }
+    }
+
+    fn statement(&mut self, node: &Node, env: &mut Env) {
+        match node {
+            Node::FunctionDeclaration { body, .. } => self.statement(body, &mut Env::new()),
+            Node::ImplBlock { methods, .. } => {
+                for method in methods { self.statement(method, &mut Env::new()); }
+            }
+            Node::BlockStatement { body, .. } => self.block(body, env),
+            Node::VariableDeclaration { identifier, data_type, initializer, .. } => {
+                let range = initializer.as_ref().and_then(|init| self.eval(init, env));
+                match range {
+                    Some(r) if matches!(data_type.as_str(), "int" | "auto") => { env.insert(identifier.clone(), r); }
+                    _ => { env.remove(identifier); }
+                }
+            }
+            Node::ExpressionStatement { expression } => self.statement(expression, env),
+            Node::AssignmentExpression { left, right, .. } => {
+                let range = self.eval(right, env);
+                if let Node::Identifier { name, .. } = &**left {
+                    match range {
+                        Some(r) => { env.insert(name.clone(), r); }
+                        None => { env.remove(name); }
+                    }
+                }
+            }
+            Node::IfStatement { test, consequent, alternate, .. } => {
+                self.eval(test, env);
+                let mut then_env = env.clone();
+                self.statement(consequent, &mut then_env);
+                let mut else_env = env.clone();
+                if let Some(alt) = alternate { self.statement(alt, &mut else_env); }
+                *env = join(&then_env, &else_env);
+            }
+            Node::WhileStatement { test, body, .. } => {
+                forget_assigned(body, env);
+                self.eval(test, env);
+                let mut body_env = env.clone();
+                self.statement(body, &mut body_env);
+            }
+            Node::ForStatement { init, test, update, body, .. } => self.for_loop(init.as_deref(), test.as_deref(), update.as_deref(), body, env),
+            Node::ReturnStatement { argument: Some(arg), .. } => { self.eval(arg, env); }
+            _ => {}
+        }
+    }
+
+    /// A loop `for (let i = a; i < n; i = i + s)` with constant `a`, `n` and
+    /// `s > 0` runs a known number of times, so `x = x + e` in its body can be
+    /// bounded by that count. Any other loop forgets what its body assigns.
+    fn for_loop(&mut self, init: Option<&Node>, test: Option<&Node>, update: Option<&Node>, body: &Node, env: &mut Env) {
+        let mut loop_env = env.clone();
+        if let Some(init) = init { self.statement(init, &mut loop_env); }
+        let Some((var, start, end, step)) = counted_loop(init, test, update, &loop_env) else {
+            forget_assigned(body, &mut loop_env);
+            if let Some(test) = test { self.eval(test, &loop_env); }
+            let mut body_env = loop_env.clone();
+            self.statement(body, &mut body_env);
+            forget_assigned(body, env);
+            return;
+        };
+        let trips = if end < start { 0 } else { (end - start) / step + 1 };
+        if trips == 0 { return; }
+        let stmts = match body {
+            Node::BlockStatement { body, .. } => body.as_slice(),
+            other => std::slice::from_ref(other),
+        };
+        // Accumulators keep their starting bounds; anything else the body assigns is unknown.
+        let accumulators: Env = stmts.iter()
+            .filter_map(accumulation)
+            .filter_map(|(name, ..)| Some((name.to_string(), *loop_env.get(name)?)))
+            .collect();
+        let names: Vec<String> = accumulators.keys().cloned().collect();
+        forget_assigned(body, &mut loop_env);
+        loop_env.extend(accumulators);
+        loop_env.insert(var.clone(), Range { lo: start, hi: end });
+        for stmt in stmts {
+            let Some((name, operator, operand, position)) = accumulation(stmt) else {
+                self.statement(stmt, &mut loop_env);
+                continue;
+            };
+            let (Some(acc), Some(e)) = (loop_env.get(name).copied(), self.eval(operand, &loop_env)) else {
+                loop_env.remove(name);
+                continue;
+            };
+            // Over all iterations `x` moves by at most `trips` times the operand's bounds.
+            let total = match operator {
+                "+" => Range { lo: acc.lo + trips.saturating_mul(e.lo).min(0), hi: acc.hi + trips.saturating_mul(e.hi).max(0) },
+                _ => Range { lo: acc.lo - trips.saturating_mul(e.hi).max(0), hi: acc.hi - trips.saturating_mul(e.lo).min(0) },
+            };
+            if total.fits() {
+                loop_env.insert(name.to_string(), total);
+            } else {
+                self.warn(position, total);
+                loop_env.remove(name);
+            }
+        }
+        forget_assigned(body, env);
+        for name in names {
+            if let Some(range) = loop_env.remove(&name) { env.insert(name, range); }
+        }
+    }
+}
+
+/// The variable, first value, last value and step of a counted `for` loop.
+fn counted_loop(init: Option<&Node>, test: Option<&Node>, update: Option<&Node>, env: &Env) -> Option<(String, i128, i128, i128)> {
+    let Some(Node::VariableDeclaration { identifier: var, .. }) = init else { return None };
+    let start = env.get(var).filter(|r| r.lo == r.hi)?.lo;
+    let Some(Node::BinaryExpression { operator, left, right, .. }) = test else { return None };
+    if !matches!(&**left, Node::Identifier { name, .. } if name == var) { return None; }
+    let bound = match &**right {
+        Node::Literal { value: LiteralValue::Int(n), .. } => *n as i128,
+        Node::Identifier { name, .. } => env.get(name).filter(|r| r.lo == r.hi)?.lo,
+        _ => return None,
+    };
+    let end = match operator.as_str() { "<" => bound - 1, "<=" => bound, _ => return None };
+    let (name, op, operand, _) = accumulation(update?)?;
+    let Node::Literal { value: LiteralValue::Int(step), .. } = operand else { return None };
+    (name == var && op == "+" && *step > 0).then(|| (var.clone(), start, end, *step as i128))
+}
+
+/// `x = x + e` or `x = x - e`, as a statement or a bare expression.
+fn accumulation(node: &Node) -> Option<(&str, &str, &Node, &Option<Pos>)> {
+    let node = match node { Node::ExpressionStatement { expression } => &**expression, other => other };
+    let Node::AssignmentExpression { left, right, .. } = node else { return None };
+    let Node::Identifier { name, .. } = &**left else { return None };
+    let Node::BinaryExpression { operator, left: lhs, right: operand, position, .. } = &**right else { return None };
+    let same = matches!(&**lhs, Node::Identifier { name: n, .. } if n == name);
+    (same && matches!(operator.as_str(), "+" | "-")).then_some((name.as_str(), operator.as_str(), &**operand, position))
+}
+
+/// Drops the bounds of every variable that `node` assigns to, anywhere inside it.
+fn forget_assigned(node: &Node, env: &mut Env) {
+    if let Ok(value) = serde_json::to_value(node) { forget_in(&value, env); }
+}
+
+fn forget_in(value: &serde_json::Value, env: &mut Env) {
+    match value {
+        serde_json::Value::Object(obj) => {
+            if obj.get("type").and_then(|t| t.as_str()) == Some("AssignmentExpression") {
+                if let Some(name) = obj["left"].get("name").and_then(|n| n.as_str()) { env.remove(name); }
+            }
+            for v in obj.values() { forget_in(v, env); }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items { forget_in(item, env); }
+        }
+        _ => {}
+    }
+}
+
+/// Bounds known on both sides of a branch, widened to cover both.
+fn join(a: &Env, b: &Env) -> Env {
+    a.iter().filter_map(|(name, ra)| Some((name.clone(), ra.hull(*b.get(name)?)))).collect()
+}
+
+/// Warnings for arithmetic in `program` that may overflow `int`.
+pub fn check_overflow(program: &Node) -> Vec<Diagnostic> {
+    let mut analysis = Analysis::default();
+    if let Node::Program { body } = program { analysis.block(body, &mut Env::new()); }
+    analysis.warnings
+}
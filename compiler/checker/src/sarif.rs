@@ -0,0 +1,51 @@
+//! Conversion of checker diagnostics into a minimal SARIF 2.1.0 log, the format
+//! consumed by GitHub code scanning.
+
+use crate::Diagnostic;
+use serde_json::{json, Value};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn level(code: &str) -> &'static str {
+    if code.starts_with('W') { "warning" } else { "error" }
+}
+
+fn result(diag: &Diagnostic, uri: &str) -> Value {
+    let span = &diag.primary_span;
+    let mut location = json!({ "artifactLocation": { "uri": uri } });
+    // Both our spans and SARIF regions are 1-based; line 0 means the AST carried no position.
+    if span.line > 0 {
+        location["region"] = json!({
+            "startLine": span.line,
+            "startColumn": span.column.max(1),
+            "endColumn": span.column.max(1) + span.length.max(1),
+        });
+    }
+    json!({
+        "ruleId": diag.code,
+        "level": level(&diag.code),
+        "message": { "text": diag.message },
+        "locations": [{ "physicalLocation": location }],
+    })
+}
+
+/// Builds a SARIF log with one run whose results are `diags`, all located in `uri`.
+pub fn to_sarif(diags: &[Diagnostic], uri: &str) -> Value {
+    let mut rules: Vec<&str> = diags.iter().map(|d| d.code.as_str()).collect();
+    rules.sort_unstable();
+    rules.dedup();
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "fax-checker",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "results": diags.iter().map(|d| result(d, uri)).collect::<Vec<_>>(),
+        }],
+    })
+}
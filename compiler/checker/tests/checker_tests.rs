@@ -0,0 +1,1141 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn run_checker(fixture: &str) -> Output {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture].iter().collect();
+    Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .arg(path)
+        .output()
+        .expect("failed to run fax-checker")
+}
+
+fn diagnostics(output: &Output) -> Vec<serde_json::Value> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.trim().is_empty() { return vec![]; }
+    serde_json::from_str(&stderr).expect("diagnostics are not a valid JSON array")
+}
+
+#[test]
+fn test_tail_value_matching_return_type() {
+    let output = run_checker("tail_value_match.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_tail_value_mismatching_return_type() {
+    let output = run_checker("tail_value_mismatch.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 3);
+    assert_eq!(diags[0]["primary_span"]["column"], 5);
+}
+
+#[test]
+fn test_independent_errors_are_all_reported() {
+    let output = run_checker("two_type_errors.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 2);
+    assert!(diags.iter().all(|d| d["code"] == "E0308"));
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int`, found `string`");
+    assert_eq!(diags[1]["primary_span"]["label"], "expected `bool`, found `int`");
+}
+
+#[test]
+fn test_assignment_in_bool_context_warns() {
+    let output = run_checker("assignment_in_bool_context.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 2);
+    assert!(diags.iter().all(|d| d["code"] == "W0020"));
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[1]["primary_span"]["line"], 3);
+    assert_eq!(diags[1]["suggestion"]["replacement"], "==");
+}
+
+#[test]
+fn test_assignment_has_assigned_value_type() {
+    let output = run_checker("assignment_value_type.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.last().unwrap()["code"], "E0308");
+    assert_eq!(diags.last().unwrap()["primary_span"]["label"], "expected `bool`, found `int`");
+}
+
+#[test]
+fn test_println_arguments_are_checked() {
+    let output = run_checker("println_argument_error.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "cannot apply `+` to `int` and `string`");
+}
+
+#[test]
+fn test_println_rejects_unprintable_argument() {
+    let output = run_checker("println_unprintable.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0277");
+    assert_eq!(diags[0]["message"], "`Point` cannot be printed");
+    assert_eq!(diags[0]["primary_span"]["column"], 18);
+}
+
+#[test]
+fn test_fixed_array_with_literal_size() {
+    let output = run_checker("fixed_array_literal_size.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_fixed_array_with_const_size() {
+    let output = run_checker("fixed_array_const_size.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_fixed_array_with_runtime_size() {
+    let output = run_checker("fixed_array_runtime_size.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0435");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+}
+
+#[test]
+fn test_array_literal_of_declared_length() {
+    let output = run_checker("fixed_array_length_match.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_array_literal_of_wrong_length() {
+    let output = run_checker("fixed_array_length_mismatch.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["message"], "mismatched array length");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected an array of 3 elements, found 2");
+}
+
+#[test]
+fn test_sarif_output() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "tail_value_mismatch.json"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .args(["--format", "sarif", "--source", "src/double.fax"])
+        .arg(path)
+        .output()
+        .expect("failed to run fax-checker");
+    assert!(!output.status.success());
+    let log: serde_json::Value = serde_json::from_slice(&output.stdout).expect("SARIF log is not valid JSON");
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"][0]["tool"]["driver"]["name"], "fax-checker");
+    let result = &log["runs"][0]["results"][0];
+    assert_eq!(result["ruleId"], "E0308");
+    assert_eq!(result["level"], "error");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/double.fax");
+    assert_eq!(location["region"]["startLine"], 3);
+    assert_eq!(location["region"]["startColumn"], 5);
+}
+
+#[test]
+fn test_binding_void_call_is_error() {
+    let output = run_checker("void_binding.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["message"], "`void` is not a value");
+    assert_eq!(diags[0]["primary_span"]["column"], 9);
+}
+
+#[test]
+fn test_void_call_as_statement() {
+    let output = run_checker("void_call_statement.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_narrowing_cast_warns() {
+    let output = run_checker("cast_float_to_int.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0024");
+    assert_eq!(diags[0]["message"], "cast may lose data");
+}
+
+#[test]
+fn test_widening_cast_is_silent() {
+    let output = run_checker("cast_int_to_float.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_non_primitive_cast() {
+    let output = run_checker("cast_string_to_int.json");
+    assert!(!output.status.success());
+    assert_eq!(diagnostics(&output)[0]["code"], "E0605");
+}
+
+#[test]
+fn test_labeled_block_breaks_with_value() {
+    let output = run_checker("labeled_block_break_value.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_labeled_block_break_values_mismatch() {
+    let output = run_checker("labeled_block_break_mismatch.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int`, found `string`");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+}
+
+#[test]
+fn test_break_to_undeclared_label() {
+    let output = run_checker("labeled_block_undeclared.json");
+    assert!(!output.status.success());
+    assert_eq!(diagnostics(&output)[0]["code"], "E0426");
+}
+
+#[test]
+fn test_nested_struct_literal_complete() {
+    let output = run_checker("nested_struct_literal_complete.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_nested_struct_literal_missing_field() {
+    let output = run_checker("nested_struct_literal_missing.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0063");
+    assert_eq!(diags[0]["primary_span"]["label"], "missing field `inner.x`");
+    assert_eq!(diags[0]["primary_span"]["column"], 22);
+}
+
+#[test]
+fn test_empty_declarations_warn() {
+    let output = run_checker("empty_struct.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 2);
+    assert!(diags.iter().all(|d| d["code"] == "W0025" && d["message"] == "empty declaration"));
+    assert_eq!(diags[1]["primary_span"]["line"], 2);
+}
+
+#[test]
+fn test_non_empty_declarations_are_silent() {
+    let output = run_checker("non_empty_struct.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_ref_argument_accepts_place() {
+    let output = run_checker("ref_argument_place.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_ref_argument_rejects_temporary() {
+    let output = run_checker("ref_argument_temporary.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0716");
+    assert_eq!(diags[0]["message"], "temporary value borrowed");
+    assert_eq!(diags[0]["primary_span"]["column"], 6);
+}
+
+#[test]
+fn test_explain_prints_extended_information() {
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .args(["--explain", "E0382"])
+        .output()
+        .expect("failed to run fax-checker");
+    assert!(output.status.success());
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.starts_with("E0382"));
+    assert!(text.contains("moved value"));
+}
+
+#[test]
+fn test_explain_unknown_code() {
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .args(["--explain", "E9999"])
+        .output()
+        .expect("failed to run fax-checker");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_store_through_pointer() {
+    let output = run_checker("pointer_store.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_store_through_pointer_mismatch() {
+    let output = run_checker("pointer_store_mismatch.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int`, found `string`");
+    assert_eq!(diags[0]["primary_span"]["length"], 2);
+}
+
+#[test]
+fn test_call_to_undefined_function() {
+    let output = run_checker("undefined_function.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0425");
+    assert_eq!(diags[0]["message"], "cannot find function `frobnicate` in this scope");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+}
+
+#[test]
+fn test_use_before_declaration() {
+    let output = run_checker("use_before_declaration.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0425");
+    assert_eq!(diags[0]["message"], "cannot find value `y` in this scope");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[0]["primary_span"]["column"], 18);
+}
+
+#[test]
+fn test_loop_variable_used_after_loop() {
+    let output = run_checker("loop_variable_after_loop.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0425");
+    assert_eq!(diags[0]["message"], "cannot find value `i` in this scope");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[0]["secondary_spans"][0]["line"], 1);
+    assert_eq!(diags[0]["secondary_spans"][0]["column"], 6);
+}
+
+#[test]
+fn test_impl_block_on_known_struct() {
+    let output = run_checker("impl_known_struct.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_impl_block_on_unknown_type() {
+    let output = run_checker("impl_unknown_type.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0116");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+}
+
+#[test]
+fn test_duplicate_method_across_impl_blocks() {
+    let output = run_checker("impl_duplicate_method.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0201");
+    assert_eq!(diags[0]["primary_span"]["line"], 7);
+}
+
+#[test]
+fn test_chained_method_calls_resolve_return_types() {
+    let output = run_checker("method_chain.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_chained_method_call_type_mismatch() {
+    let output = run_checker("method_chain_mismatch.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 10);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `string`, found `int`");
+}
+
+#[test]
+fn test_reserved_prefix_declaration_warns() {
+    let output = run_checker("reserved_prefix.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0026");
+    assert_eq!(diags[0]["message"], "`__fax_tmp` uses a reserved prefix");
+    assert_eq!(diags[0]["primary_span"]["line"], 1);
+}
+
+#[test]
+fn test_if_let_binding_is_usable_in_body() {
+    let output = run_checker("if_let_binding.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_if_let_binding_is_not_visible_after_body() {
+    let output = run_checker("if_let_outside_body.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0425");
+    assert_eq!(diags[0]["primary_span"]["line"], 5);
+}
+
+#[test]
+fn test_if_let_requires_optional_producer() {
+    let output = run_checker("if_let_not_optional.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `optional<_>`, found `int`");
+}
+
+#[test]
+fn test_bitwise_and_with_ungrouped_comparison_warns() {
+    let output = run_checker("bitwise_comparison_precedence.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0027");
+    assert_eq!(diags[0]["primary_span"]["column"], 18);
+    assert_eq!(diags[0]["note"], "add parentheses to make the grouping explicit: `a & (b == c)` or `(a & b) == c`");
+}
+
+#[test]
+fn test_parenthesized_bitwise_and_is_silent() {
+    let output = run_checker("bitwise_comparison_grouped.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_consistent_returns_match_declared_type() {
+    let output = run_checker("returns_consistent.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_return_matches_declared_type() {
+    let output = run_checker("return_matches_declared.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_return_mismatches_declared_type() {
+    let output = run_checker("return_mismatch_declared.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int` because of return type, found `string`");
+}
+
+#[test]
+fn test_bare_return_in_non_void_function() {
+    let output = run_checker("return_missing_value.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0069");
+    assert_eq!(diags[0]["primary_span"]["label"], "return type is `int`");
+}
+
+#[test]
+fn test_disagreeing_returns_in_auto_function() {
+    let output = run_checker("returns_disagree.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 5);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int` because of an earlier return, found `string`");
+    assert_eq!(diags[0]["secondary_spans"][0]["line"], 3);
+}
+
+#[test]
+fn test_auto_return_type_is_inferred_for_callers() {
+    let output = run_checker("returns_auto_inferred.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 7);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `string`, found `int`");
+}
+
+#[test]
+fn test_value_widens_to_optional() {
+    let output = run_checker("optional_widening.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_optional_does_not_narrow_implicitly() {
+    let output = run_checker("optional_to_plain.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int`, found `optional<int>`");
+    assert!(diags[0]["note"].as_str().unwrap().contains("may be `null`"));
+}
+
+#[test]
+fn test_optional_narrows_after_null_check() {
+    let output = run_checker("optional_null_check.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_redeclaring_loop_variable_in_body_warns() {
+    let output = run_checker("loop_variable_shadowed.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0028");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[0]["secondary_spans"][0]["line"], 1);
+    assert_eq!(diags[0]["secondary_spans"][0]["column"], 6);
+}
+
+#[test]
+fn test_differently_named_loop_local_is_silent() {
+    let output = run_checker("loop_body_local.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_emit_dependency_graph() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "deps_program.json"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .args(["--emit", "deps"])
+        .arg(path)
+        .output()
+        .expect("failed to run fax-checker");
+    assert!(output.status.success());
+    let graph: serde_json::Value = serde_json::from_slice(&output.stdout).expect("dependency graph is not valid JSON");
+    assert_eq!(graph["main"]["kind"], "function");
+    assert_eq!(graph["main"]["calls"], serde_json::json!(["helper"]));
+    assert_eq!(graph["main"]["uses"], serde_json::json!(["Point"]));
+    assert_eq!(graph["helper"]["calls"], serde_json::json!([]));
+    assert_eq!(graph["helper"]["uses"], serde_json::json!(["Point"]));
+    assert_eq!(graph["Line"]["contains"], serde_json::json!(["Point"]));
+    assert_eq!(graph["Point"]["contains"], serde_json::json!([]));
+}
+
+#[test]
+fn test_break_after_return_is_unreachable() {
+    let output = run_checker("break_after_return.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0029");
+    assert_eq!(diags[0]["message"], "unreachable control-flow statement");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+    assert_eq!(diags[0]["secondary_spans"][0]["line"], 3);
+}
+
+#[test]
+fn test_if_condition_must_be_bool() {
+    let output = run_checker("if_int_condition.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 3);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `bool` for condition, found `int`");
+}
+
+#[test]
+fn test_comparison_is_a_valid_condition() {
+    let output = run_checker("while_comparison_condition.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_for_loop_test_must_be_bool() {
+    let output = run_checker("for_non_bool_test.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["column"], 22);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `bool` for condition, found `int`");
+}
+
+#[test]
+fn test_for_init_variable_is_visible_in_body() {
+    let output = run_checker("for_init_scoped.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_else_after_return_warns() {
+    let output = run_checker("redundant_else.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0030");
+    assert_eq!(diags[0]["message"], "unnecessary `else` after `return`");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+    assert_eq!(diags[0]["secondary_spans"][0]["line"], 3);
+}
+
+#[test]
+fn test_else_after_non_terminating_branch_is_silent() {
+    let output = run_checker("needed_else.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_const_without_initializer_is_error() {
+    let output = run_checker("const_uninitialized.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0317");
+    assert_eq!(diags[0]["message"], "`const` `LIMIT` must be initialized");
+    assert_eq!(diags[0]["primary_span"]["line"], 1);
+}
+
+#[test]
+fn test_initialized_const_is_accepted() {
+    let output = run_checker("const_initialized.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_keyword_struct_initializer() {
+    let output = run_checker("struct_init_keyword.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_positional_struct_initializer() {
+    let output = run_checker("struct_init_positional.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_mixed_struct_initializer_is_error() {
+    let output = run_checker("struct_init_mixed.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0784");
+    assert_eq!(diags[0]["primary_span"]["column"], 30);
+}
+
+#[test]
+fn test_positional_struct_initializer_needs_every_field() {
+    let output = run_checker("struct_init_positional_count.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0061");
+    assert_eq!(diags[0]["message"], "struct `Point` takes 2 positional values, found 1");
+}
+
+#[test]
+fn test_bool_match_covering_both_values() {
+    let output = run_checker("bool_match_exhaustive.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_bool_match_missing_value() {
+    let output = run_checker("bool_match_missing_arm.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0004");
+    assert_eq!(diags[0]["message"], "non-exhaustive patterns: `false` not covered");
+    assert_eq!(diags[0]["primary_span"]["line"], 3);
+}
+
+#[test]
+fn test_bool_match_redundant_wildcard() {
+    let output = run_checker("bool_match_redundant_wildcard.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0035");
+    assert_eq!(diags[0]["message"], "unreachable pattern");
+    assert_eq!(diags[0]["primary_span"]["line"], 6);
+}
+
+#[test]
+fn test_too_many_params_warns() {
+    let output = run_checker("too_many_params.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0033");
+    assert_eq!(diags[0]["message"], "function `configure` has too many parameters");
+    assert_eq!(diags[0]["primary_span"]["label"], "8 parameters, more than the limit of 7");
+}
+
+#[test]
+fn test_max_params_is_configurable() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "too_many_params.json"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .args(["--max-params", "2"])
+        .arg(path)
+        .output()
+        .expect("failed to run fax-checker");
+    let diags = diagnostics(&output);
+    let flagged: Vec<&serde_json::Value> = diags.iter().map(|d| &d["message"]).collect();
+    assert_eq!(flagged, ["function `configure` has too many parameters", "function `blend` has too many parameters"]);
+}
+
+#[test]
+fn test_null_coalesce_unwraps_optional() {
+    let output = run_checker("null_coalesce_optional.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_null_coalesce_on_non_optional_warns() {
+    let output = run_checker("null_coalesce_non_optional.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0037");
+    assert_eq!(diags[0]["primary_span"]["label"], "`int` is not an optional type");
+}
+
+#[test]
+fn test_int_match_distinct_arms() {
+    let output = run_checker("int_match_distinct_arms.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_int_match_duplicate_arm() {
+    let output = run_checker("int_match_duplicate_arm.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0080");
+    assert_eq!(diags[0]["message"], "unreachable arm: duplicate value `1`");
+    assert_eq!(diags[0]["primary_span"]["line"], 5);
+    assert_eq!(diags[0]["secondary_spans"][0]["line"], 4);
+}
+
+#[test]
+fn test_variant_payload_bound_with_its_type() {
+    let output = run_checker("variant_payload_match.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_variant_payload_bound_with_wrong_type() {
+    let output = run_checker("variant_payload_mismatch.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int`, found `string`");
+    assert_eq!(diags[0]["primary_span"]["line"], 6);
+}
+
+#[test]
+fn test_allowed_warning_is_not_emitted() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "redundant_else.json"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .args(["--allow", "W0012", "--allow", "W0030"])
+        .arg(&path)
+        .output()
+        .expect("failed to run fax-checker");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .env("FAX_ALLOW", "W0012,w0030")
+        .arg(&path)
+        .output()
+        .expect("failed to run fax-checker");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_mutually_recursive_functions_unreachable_from_main() {
+    let output = run_checker("unreachable_cycle.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 2);
+    assert!(diags.iter().all(|d| d["code"] == "W0031"));
+    assert_eq!(diags[0]["message"], "function `ping` is never reachable");
+    assert_eq!(diags[1]["message"], "function `pong` is never reachable");
+}
+
+#[test]
+fn test_function_called_at_top_level_is_reachable() {
+    let output = run_checker("top_level_call_root.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+fn run_checker_with_overflow_checks(fixture: &str) -> Output {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture].iter().collect();
+    Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .arg("--check-overflow")
+        .arg(path)
+        .output()
+        .expect("failed to run fax-checker")
+}
+
+#[test]
+fn test_accumulation_in_counted_loop_may_overflow() {
+    let output = run_checker_with_overflow_checks("overflow_in_loop.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0032");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+    assert_eq!(diags[0]["primary_span"]["label"], "the result can reach 10000000000000000000");
+    assert!(diagnostics(&run_checker("overflow_in_loop.json")).is_empty());
+}
+
+#[test]
+fn test_bounded_accumulation_in_counted_loop() {
+    let output = run_checker_with_overflow_checks("bounded_loop_sum.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_self_inside_method() {
+    let output = run_checker("impl_known_struct.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_self_in_free_function() {
+    let output = run_checker("self_in_free_fn.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0424");
+    assert_eq!(diags[0]["message"], "`self` is not available in this context");
+    assert_eq!(diags[0]["primary_span"]["line"], 4);
+}
+
+fn run_checker_with_type_assertions(fixture: &str) -> Output {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture].iter().collect();
+    Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .arg("--type-assertions")
+        .arg(path)
+        .output()
+        .expect("failed to run fax-checker")
+}
+
+#[test]
+fn test_type_assertions_hold() {
+    let output = run_checker_with_type_assertions("type_assertions.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_type_assertion_failure() {
+    let output = run_checker_with_type_assertions("type_assertion_failure.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["message"], "type assertion failed");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `float`, found `int`");
+}
+
+#[test]
+fn test_type_assertions_need_flag() {
+    let output = run_checker("type_assertions.json");
+    assert!(!output.status.success());
+    assert_eq!(diagnostics(&output)[0]["code"], "E0658");
+}
+
+#[test]
+fn test_homogeneous_array_indexes_to_element_type() {
+    let output = run_checker("array_homogeneous.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_mixed_array_elements_are_rejected() {
+    let output = run_checker("array_mixed_elements.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int`, found `string`");
+    assert_eq!(diags[0]["primary_span"]["column"], 17);
+    assert_eq!(diags[0]["secondary_spans"][0]["column"], 14);
+}
+
+#[test]
+fn test_string_subscript_is_rejected() {
+    let output = run_checker("array_string_index.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0277");
+    assert_eq!(diags[0]["message"], "the type `int[]` cannot be indexed by `string`");
+}
+
+#[test]
+fn test_auto_variable_takes_initializer_type() {
+    let output = run_checker("auto_inferred_misuse.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `string`, found `int`");
+}
+
+#[test]
+fn test_auto_variable_without_initializer_needs_annotation() {
+    let output = run_checker("auto_without_initializer.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0282");
+    assert_eq!(diags[0]["message"], "type annotations needed");
+}
+
+#[test]
+fn test_tuple_return_destructures_into_element_types() {
+    let output = run_checker("tuple_return_match.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_tuple_return_with_wrong_arity_is_rejected() {
+    let output = run_checker("tuple_return_arity.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `tuple<int, string>` because of return type, found `tuple<int, string, int>`");
+    assert_eq!(diags[0]["note"], "the function returns 2 values, but this `return` has 3");
+}
+
+#[test]
+fn test_struct_literal_field_of_wrong_type() {
+    let output = run_checker("struct_literal_wrong_field_type.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "field `y` expects `int`, found `string`");
+}
+
+#[test]
+fn test_struct_literal_unknown_field() {
+    let output = run_checker("struct_literal_unknown_field.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0560");
+    assert_eq!(diags[0]["message"], "struct `Point` has no field named `z`");
+    assert_eq!(diags[1]["code"], "E0063");
+}
+
+#[test]
+fn test_struct_literal_duplicate_field() {
+    let output = run_checker("struct_literal_duplicate_field.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0062");
+    assert_eq!(diags[0]["message"], "field `x` specified more than once");
+}
+
+#[test]
+fn test_reassigning_let_binding_is_rejected() {
+    let output = run_checker("let_reassign.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0384");
+    assert_eq!(diags[0]["secondary_spans"][0]["line"], 2);
+    assert_eq!(diags[0]["suggestion"]["replacement"], "var");
+}
+
+#[test]
+fn test_compound_assignment_to_let_binding_is_rejected() {
+    let output = run_checker("let_compound_assign.json");
+    assert!(!output.status.success());
+    assert_eq!(diagnostics(&output)[0]["code"], "E0384");
+}
+
+#[test]
+fn test_var_binding_can_be_reassigned() {
+    let output = run_checker("var_reassign.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_recursive_auto_return_needs_annotation() {
+    let output = run_checker("recursive_auto_return.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0720");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+}
+
+#[test]
+fn test_recursive_function_with_annotated_return() {
+    let output = run_checker("recursive_annotated_return.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_println_placeholder_warning_points_at_placeholder() {
+    let output = run_checker("println_placeholder.json");
+    assert!(output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0038");
+    // The first `{}` of `"a\tb {} {}"`: the escape shifts it one column past `start + 1 + index`.
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[0]["primary_span"]["column"], 19);
+    assert_eq!(diags[0]["primary_span"]["length"], 2);
+}
+
+#[test]
+fn test_bitflags_enum_combines_variants() {
+    let output = run_checker("bitflags_valid.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_bitflags_discriminant_must_be_power_of_two() {
+    let output = run_checker("bitflags_not_power_of_two.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0080");
+    assert_eq!(diags[0]["message"], "discriminant of bitflags variant `ReadWrite` is not a power of two");
+    assert_eq!(diags[0]["primary_span"]["column"], 39);
+}
+
+#[test]
+fn test_bitwise_or_on_plain_enum_is_error() {
+    let output = run_checker("enum_bitwise_without_bitflags.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0369");
+}
+
+fn run_checker_with_diagnostic_format(fixture: &str, framing: &str) -> Output {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture].iter().collect();
+    Command::new(env!("CARGO_BIN_EXE_fax-checker"))
+        .args(["--diagnostic-format", framing])
+        .arg(path)
+        .output()
+        .expect("failed to run fax-checker")
+}
+
+#[test]
+fn test_array_framing_prints_one_json_array() {
+    let output = run_checker_with_diagnostic_format("two_type_errors.json", "array");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.trim_end().lines().count(), 1);
+    let diags: Vec<serde_json::Value> = serde_json::from_str(&stderr).expect("stderr is not a JSON array");
+    assert_eq!(diags.len(), 2);
+    assert!(diags.iter().all(|d| d["code"] == "E0308"));
+}
+
+#[test]
+fn test_ndjson_framing_prints_one_object_per_line() {
+    let output = run_checker_with_diagnostic_format("two_type_errors.json", "ndjson");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<serde_json::Value> = stderr.lines()
+        .map(|line| serde_json::from_str(line).expect("line is not a JSON object"))
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|d| d.is_object() && d["code"] == "E0308"));
+    assert_eq!(lines[1]["primary_span"]["label"], "expected `bool`, found `int`");
+}
+
+#[test]
+fn test_unknown_diagnostic_framing_is_rejected() {
+    let output = run_checker_with_diagnostic_format("two_type_errors.json", "xml");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_null_checked_pointer_passes_to_non_null_parameter() {
+    let output = run_checker("ptr_checked_non_null.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_maybe_null_pointer_rejected_by_non_null_parameter() {
+    let output = run_checker("ptr_maybe_null.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["label"], "argument #1 expected `ptr<int>`, found `optional<ptr<int>>`");
+    assert!(diags[0]["note"].as_str().unwrap().contains("`ptr<int>?`"));
+}
+
+#[test]
+fn test_generic_struct_field_has_type_argument() {
+    let output = run_checker_with_type_assertions("generic_struct_field.json");
+    assert!(output.status.success());
+    assert!(diagnostics(&output).is_empty());
+}
+
+#[test]
+fn test_generic_struct_field_assignment_mismatch() {
+    let output = run_checker("generic_struct_field_mismatch.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0308");
+    assert_eq!(diags[0]["primary_span"]["line"], 5);
+    assert_eq!(diags[0]["primary_span"]["label"], "expected `int`, found `string`");
+}
+
+#[test]
+fn test_generic_struct_without_type_argument() {
+    let output = run_checker("generic_struct_missing_type_argument.json");
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0107");
+    assert_eq!(diags[0]["message"], "struct `Box` takes 1 type argument but 0 were supplied");
+}
@@ -0,0 +1,177 @@
+//! A token-based formatter: re-indents and re-spaces a token stream produced
+//! with `Lexer::preserve_trivia`, keeping comments and the author's line breaks.
+
+use crate::{Token, TokenType};
+
+/// Layout settings for `format_tokens`.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Spaces per indentation level; ignored when `use_tabs` is set.
+    pub indent_width: usize,
+    /// Indent with one tab per level instead of spaces.
+    pub use_tabs: bool,
+    /// Longer runs of empty lines are collapsed to this many.
+    pub max_blank_lines: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions { indent_width: 4, use_tabs: false, max_blank_lines: 1 }
+    }
+}
+
+impl FormatOptions {
+    fn indent(&self, level: usize) -> String {
+        if self.use_tabs { "\t".repeat(level) } else { " ".repeat(self.indent_width * level) }
+    }
+}
+
+/// Whether a space goes between two adjacent tokens on the same line.
+fn space_between(prev: &TokenType, next: &TokenType) -> bool {
+    use TokenType::*;
+    if matches!(prev, LeftParen | LeftBracket | Dot | Range | RangeInclusive | DoubleColon | LogicalNot | BitwiseNot) { return false; }
+    if matches!(next, RightParen | RightBracket | Semicolon | Comma | Dot | Range | RangeInclusive | DoubleColon | Colon | Question) { return false; }
+    // Calls and indexing: `f(x)`, `a[i]`, `f(x)(y)`; keywords such as `if (` keep their space.
+    let callee = matches!(prev, Identifier(_) | RightParen | RightBracket);
+    !(callee && matches!(next, LeftParen | LeftBracket))
+}
+
+/// Whether a `-`, `&`, `*` or `**` after `prev` is a prefix operator:
+/// anything but the end of an operand, or nothing at all at the start of a line.
+fn starts_operand(prev: Option<&TokenType>) -> bool {
+    use TokenType::*;
+    !matches!(
+        prev,
+        Some(Identifier(_) | IntegerLiteral(_) | FloatLiteral(_) | StringLiteral(_) | ByteStringLiteral(_) | CharLiteral(_)
+            | BooleanLiteral(_) | HexLiteral(_) | BinaryLiteral(_) | OctalLiteral(_) | True | False | Null | SelfValue
+            | Int | Float | Bool | String | Char | Void | RightParen | RightBracket)
+    )
+}
+
+/// Marks the `<` and `>` (or `>>`) tokens that enclose type arguments, as in
+/// `ptr<int>` or `Box<array<int, 3>>`: a `<` after a type name whose closing
+/// bracket follows on the same line with only types, sizes and commas between.
+fn type_argument_brackets(tokens: &[Token]) -> Vec<bool> {
+    use TokenType::*;
+    let mut marks = vec![false; tokens.len()];
+    let mut prev: Option<&TokenType> = None;
+    for (open, token) in tokens.iter().enumerate() {
+        if token.token_type == LessThan && matches!(prev, Some(Identifier(_) | Ptr | Ref)) {
+            let mut depth = 0i32;
+            let mut brackets = Vec::new();
+            for (i, inner) in tokens.iter().enumerate().skip(open) {
+                match &inner.token_type {
+                    LessThan => depth += 1,
+                    GreaterThan => depth -= 1,
+                    RightShift => depth -= 2,
+                    Whitespace(text) if !text.contains('\n') => continue,
+                    Identifier(_) | Ptr | Ref | Mut | Int | Float | Bool | String | Char | Void | IntegerLiteral(_)
+                    | Comma | Question | DoubleColon => continue,
+                    _ => break,
+                }
+                if depth < 0 { break; }
+                brackets.push(i);
+                if depth == 0 {
+                    brackets.into_iter().for_each(|b| marks[b] = true);
+                    break;
+                }
+            }
+        }
+        if !matches!(token.token_type, Whitespace(_)) { prev = Some(&token.token_type); }
+    }
+    marks
+}
+
+/// Lays out `tokens` with one space between tokens, `options`' indentation at
+/// the start of each line, and at most `max_blank_lines` empty lines in a row.
+/// Line breaks come from the `Whitespace` tokens, so the input should be lexed
+/// with trivia preserved.
+pub fn format_tokens(tokens: &[Token], options: &FormatOptions) -> String {
+    let type_brackets = type_argument_brackets(tokens);
+    let mut out = String::new();
+    let mut level = 0usize;
+    let mut prev: Option<&TokenType> = None;
+    let mut line_start = true;
+    // The previous token binds to this one: a prefix operator, `@`, or a `<` opening type arguments.
+    let mut glued = false;
+    // The previous token closes type arguments, so a `[` after it is an array type: `ptr<int>[]`.
+    let mut after_type_arguments = false;
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.token_type {
+            TokenType::Eof => break,
+            TokenType::Whitespace(text) => {
+                let newlines = text.matches('\n').count();
+                if newlines > 0 && !out.is_empty() {
+                    out.push_str(&"\n".repeat(newlines.min(options.max_blank_lines + 1)));
+                    line_start = true;
+                }
+                continue;
+            }
+            TokenType::RightBrace => level = level.saturating_sub(1),
+            _ => {}
+        }
+        let attached = glued || type_brackets[i] || (after_type_arguments && token.token_type == TokenType::LeftBracket);
+        if line_start {
+            out.push_str(&options.indent(level));
+        } else if !attached && prev.is_some_and(|p| space_between(p, &token.token_type)) {
+            out.push(' ');
+        }
+        out.push_str(&token.value);
+        if token.token_type == TokenType::LeftBrace { level += 1; }
+        let prefix = matches!(token.token_type, TokenType::Minus | TokenType::BitwiseAnd | TokenType::Multiply | TokenType::Power)
+            && starts_operand(if line_start { None } else { prev });
+        let opens_type_arguments = type_brackets[i] && token.token_type == TokenType::LessThan;
+        glued = prefix || opens_type_arguments || token.token_type == TokenType::At;
+        after_type_arguments = type_brackets[i] && !opens_type_arguments;
+        prev = Some(&token.token_type);
+        line_start = false;
+    }
+    if !out.ends_with('\n') { out.push('\n'); }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    fn format(source: &str, options: &FormatOptions) -> String {
+        let tokens = Lexer::new(source).preserve_trivia(true).tokenize().expect("Failed to tokenize");
+        format_tokens(&tokens, options)
+    }
+
+    const SOURCE: &str = "fn main() {\nlet x = 1;\n\n\n\nif (x > 0) {\n// positive\nprint( x );\n}\n}\n";
+
+    #[test]
+    fn test_two_space_indent() {
+        let options = FormatOptions { indent_width: 2, ..FormatOptions::default() };
+        assert_eq!(
+            format(SOURCE, &options),
+            "fn main() {\n  let x = 1;\n\n  if (x > 0) {\n    // positive\n    print(x);\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_four_space_indent() {
+        let options = FormatOptions { indent_width: 4, max_blank_lines: 2, ..FormatOptions::default() };
+        assert_eq!(
+            format(SOURCE, &options),
+            "fn main() {\n    let x = 1;\n\n\n    if (x > 0) {\n        // positive\n        print(x);\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_tab_indent() {
+        let options = FormatOptions { use_tabs: true, ..FormatOptions::default() };
+        assert_eq!(format("fn f() {\n  return;\n}", &options), "fn f() {\n\treturn;\n}\n");
+    }
+
+    #[test]
+    fn test_type_arguments_and_prefix_operators() {
+        let source = "@bitflags\nlet p: ptr<int> = &x;\nlet ps: ptr<int>[] = [p];\nlet a: Box<array<int, 3>> = b;\nlet y = -1 - *p;\nreturn -y;\nif (a < b && c > d) {\n-x;\n}\n";
+        assert_eq!(
+            format(source, &FormatOptions::default()),
+            "@bitflags\nlet p: ptr<int> = &x;\nlet ps: ptr<int>[] = [p];\nlet a: Box<array<int, 3>> = b;\nlet y = -1 - *p;\nreturn -y;\nif (a < b && c > d) {\n    -x;\n}\n"
+        );
+    }
+}
@@ -1,6 +1,15 @@
 use std::fmt;
+use std::num::IntErrorKind;
+use std::ops::Range;
 
-#[derive(Debug, Clone, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+pub mod format;
+
+/// Serializes as `{ "type": "IntegerLiteral", "value": 42 }`; variants without
+/// data such as `Let` carry only the `type` field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
 pub enum TokenType {
     // Keywords
     Let,
@@ -9,22 +18,35 @@ pub enum TokenType {
     Fn,
     Struct,
     Enum,
+    Impl,
     If,
     Else,
     While,
     For,
     Return,
+    Match,
+    Break,
+    Continue,
+    Loop,
+    Import,
     Pub,
     Priv,
     Static,
+    Ptr,
+    Ref,
+    /// `self`
+    SelfValue,
+    Mut,
     Int,
     Float,
     Bool,
     String,
     Char,
     Void,
+    As,
     True,
     False,
+    Null,
 
     // Identifiers
     Identifier(String),
@@ -33,6 +55,8 @@ pub enum TokenType {
     IntegerLiteral(i64),
     FloatLiteral(f64),
     StringLiteral(String),
+    ByteStringLiteral(Vec<u8>),
+    CharLiteral(char),
     BooleanLiteral(bool),
     HexLiteral(i64),
     BinaryLiteral(i64),
@@ -44,6 +68,7 @@ pub enum TokenType {
     Multiply,
     Divide,
     Modulo,
+    Power,
     Assign,
     Equal,
     NotEqual,
@@ -65,6 +90,7 @@ pub enum TokenType {
     MultiplyAssign,
     DivideAssign,
     ModuloAssign,
+    PowerAssign,
 
     // Punctuation
     LeftParen,
@@ -76,20 +102,39 @@ pub enum TokenType {
     Semicolon,
     Comma,
     Dot,
+    Range,
+    RangeInclusive,
+    Question,
+    NullCoalesce,
     Colon,
     DoubleColon,
     Arrow,
+    FatArrow,
+    At,
+    /// A block label such as `'outer`, without the quote.
+    Label(String),
+
+    // Trivia, only produced when `Lexer::preserve_trivia` is enabled
+    LineComment(String),
+    BlockComment(String),
+    Whitespace(String),
 
     // Special
     Eof,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Token {
     pub token_type: TokenType,
     pub value: String,
     pub line: usize,
     pub column: usize,
+    /// Byte range of the token in the source, excluding surrounding whitespace and comments.
+    pub span: Range<usize>,
+    /// Line and column just past the token's last character, so a token on one
+    /// line is `end_column - column` characters long.
+    pub end_line: usize,
+    pub end_column: usize,
 }
 
 impl fmt::Display for Token {
@@ -156,12 +201,79 @@ impl LexerError {
     }
 }
 
+/// Parses the digits of an integer literal, telling a value too large for `i64`
+/// apart from a malformed one. `kind` names the base in the error message.
+fn parse_integer(digits: &str, radix: u32, kind: &str, literal: &str, line: usize, column: usize, position: usize) -> Result<i64, LexerError> {
+    i64::from_str_radix(digits, radix).map_err(|e| {
+        let message = match e.kind() {
+            IntErrorKind::PosOverflow => format!("integer literal out of range for 64-bit signed integer: {}", literal),
+            _ => format!("Invalid {} number: {}", kind, literal),
+        };
+        LexerError::new(message, line, column, position)
+    })
+}
+
+/// Returns the token type of a reserved word, or `None` if `s` is not a keyword.
+pub fn keyword_token(s: &str) -> Option<TokenType> {
+    match s {
+        "let" => Some(TokenType::Let),
+        "var" => Some(TokenType::Var),
+        "const" => Some(TokenType::Const),
+        "fn" => Some(TokenType::Fn),
+        "struct" => Some(TokenType::Struct),
+        "enum" => Some(TokenType::Enum),
+        "impl" => Some(TokenType::Impl),
+        "if" => Some(TokenType::If),
+        "else" => Some(TokenType::Else),
+        "while" => Some(TokenType::While),
+        "for" => Some(TokenType::For),
+        "return" => Some(TokenType::Return),
+        "match" => Some(TokenType::Match),
+        "break" => Some(TokenType::Break),
+        "continue" => Some(TokenType::Continue),
+        "loop" => Some(TokenType::Loop),
+        "import" => Some(TokenType::Import),
+        "pub" => Some(TokenType::Pub),
+        "priv" => Some(TokenType::Priv),
+        "static" => Some(TokenType::Static),
+        "ptr" => Some(TokenType::Ptr),
+        "ref" => Some(TokenType::Ref),
+        "self" => Some(TokenType::SelfValue),
+        "mut" => Some(TokenType::Mut),
+        "int" => Some(TokenType::Int),
+        "float" => Some(TokenType::Float),
+        "bool" => Some(TokenType::Bool),
+        "string" => Some(TokenType::String),
+        "char" => Some(TokenType::Char),
+        "void" => Some(TokenType::Void),
+        "as" => Some(TokenType::As),
+        "true" => Some(TokenType::BooleanLiteral(true)),
+        "false" => Some(TokenType::BooleanLiteral(false)),
+        "null" => Some(TokenType::Null),
+        _ => None,
+    }
+}
+
+/// Whether `c` may start an identifier (Unicode `XID_Start` or `_`).
+fn is_ident_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
+}
+
+/// Whether `c` may continue an identifier (Unicode `XID_Continue`, which includes `_`).
+fn is_ident_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
+
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
     line: usize,
     column: usize,
     absolute_position: usize,
+    /// Byte offset of the cursor in the original `&str`.
+    byte_offset: usize,
+    ascii_identifiers_only: bool,
+    preserve_trivia: bool,
 }
 
 impl Lexer {
@@ -172,9 +284,28 @@ impl Lexer {
             line: 1,
             column: 1,
             absolute_position: 0,
+            byte_offset: 0,
+            ascii_identifiers_only: false,
+            preserve_trivia: false,
         }
     }
 
+    /// Restricts identifiers to `[A-Za-z_][A-Za-z0-9_]*`; any other letter in an
+    /// identifier becomes an error instead of following the Unicode rules.
+    pub fn ascii_identifiers_only(mut self, enabled: bool) -> Self {
+        self.ascii_identifiers_only = enabled;
+        self
+    }
+
+    /// Emits whitespace and comments as `Whitespace`, `LineComment` and
+    /// `BlockComment` tokens instead of skipping them, and sets every token's
+    /// `value` to its exact source text, so that concatenating the values of
+    /// all tokens reproduces the input.
+    pub fn preserve_trivia(mut self, enabled: bool) -> Self {
+        self.preserve_trivia = enabled;
+        self
+    }
+
     fn current_char(&self) -> Option<char> {
         self.input.get(self.position).copied()
     }
@@ -193,12 +324,13 @@ impl Lexer {
             }
             self.position += 1;
             self.absolute_position += 1;
+            self.byte_offset += ch.len_utf8();
         }
     }
 
     fn skip_whitespace(&mut self) {
         while let Some(ch) = self.current_char() {
-            if ch.is_whitespace() && ch != '\n' {
+            if ch.is_whitespace() {
                 self.advance();
             } else {
                 break;
@@ -206,7 +338,7 @@ impl Lexer {
         }
     }
 
-    fn skip_comment(&mut self) {
+    fn skip_comment(&mut self) -> Result<(), LexerError> {
         if self.current_char() == Some('/') && self.peek(1) == Some('/') {
             // Skip single-line comment
             while let Some(ch) = self.current_char() {
@@ -217,17 +349,58 @@ impl Lexer {
             }
         } else if self.current_char() == Some('/') && self.peek(1) == Some('*') {
             // Skip multi-line comment
+            let (start_line, start_column, start_pos) = (self.line, self.column, self.absolute_position);
             self.advance(); // skip first '/'
             self.advance(); // skip '*'
+            let mut terminated = false;
             while let Some(ch) = self.current_char() {
                 if ch == '*' && self.peek(1) == Some('/') {
                     self.advance(); // skip '*'
                     self.advance(); // skip '/'
+                    terminated = true;
                     break;
                 }
                 self.advance();
             }
+            if !terminated {
+                // Point at the `/*`, not at the end of the file where the lexer gave up.
+                return Err(LexerError::with_type(
+                    LexerErrorType::UnexpectedEof,
+                    start_line,
+                    start_column,
+                    start_pos
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads digits accepted by `is_digit`, allowing `_` as a separator between
+    /// two digits, and returns them with the separators removed.
+    fn read_digits(&mut self, is_digit: impl Fn(char) -> bool) -> Result<String, LexerError> {
+        let mut digits = String::new();
+        let mut after_digit = false;
+        while let Some(ch) = self.current_char() {
+            if ch == '_' {
+                // Leading, trailing and doubled separators are all a `_` without a digit on both sides.
+                if !after_digit || !self.peek(1).is_some_and(&is_digit) {
+                    return Err(LexerError::with_type(
+                        LexerErrorType::InvalidNumber,
+                        self.line,
+                        self.column,
+                        self.absolute_position,
+                    ));
+                }
+                after_digit = false;
+            } else if is_digit(ch) {
+                digits.push(ch);
+                after_digit = true;
+            } else {
+                break;
+            }
+            self.advance();
         }
+        Ok(digits)
     }
 
     fn read_number(&mut self) -> Result<TokenType, LexerError> {
@@ -236,21 +409,13 @@ impl Lexer {
         let start_pos = self.absolute_position;
 
         // Handle hexadecimal, binary, octal prefixes
-        if self.current_char() == Some('0') && self.peek(1) == Some('x') {
+        if self.current_char() == Some('0') && matches!(self.peek(1), Some('x' | 'X')) {
             self.advance(); // skip '0'
             self.advance(); // skip 'x'
 
-            // Read hexadecimal digits
-            while let Some(ch) = self.current_char() {
-                if ch.is_ascii_hexdigit() {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
-
+            let digits = self.read_digits(|c| c.is_ascii_hexdigit())?;
             let num_str: String = self.input[start_pos..self.position].iter().collect();
-            if num_str.len() <= 2 {
+            if digits.is_empty() {
                 return Err(LexerError::new(
                     format!("Invalid hexadecimal number: {}", num_str),
                     start_line,
@@ -259,30 +424,16 @@ impl Lexer {
                 ));
             }
 
-            let value = i64::from_str_radix(&num_str[2..], 16)
-                .map_err(|_| LexerError::new(
-                    format!("Invalid hexadecimal number: {}", num_str),
-                    start_line,
-                    start_column,
-                    start_pos
-                ))?;
+            let value = parse_integer(&digits, 16, "hexadecimal", &num_str, start_line, start_column, start_pos)?;
 
-            return Ok(TokenType::HexLiteral(value));
-        } else if self.current_char() == Some('0') && self.peek(1) == Some('b') {
+            Ok(TokenType::HexLiteral(value))
+        } else if self.current_char() == Some('0') && matches!(self.peek(1), Some('b' | 'B')) {
             self.advance(); // skip '0'
             self.advance(); // skip 'b'
 
-            // Read binary digits
-            while let Some(ch) = self.current_char() {
-                if ch == '0' || ch == '1' {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
-
+            let digits = self.read_digits(|c| c == '0' || c == '1')?;
             let num_str: String = self.input[start_pos..self.position].iter().collect();
-            if num_str.len() <= 2 {
+            if digits.is_empty() {
                 return Err(LexerError::new(
                     format!("Invalid binary number: {}", num_str),
                     start_line,
@@ -291,29 +442,18 @@ impl Lexer {
                 ));
             }
 
-            let value = i64::from_str_radix(&num_str[2..], 2)
-                .map_err(|_| LexerError::new(
-                    format!("Invalid binary number: {}", num_str),
-                    start_line,
-                    start_column,
-                    start_pos
-                ))?;
+            let value = parse_integer(&digits, 2, "binary", &num_str, start_line, start_column, start_pos)?;
 
-            return Ok(TokenType::BinaryLiteral(value));
-        } else if self.current_char() == Some('0') && self.peek(1).map_or(false, |c| ('0'..='7').contains(&c)) {
+            Ok(TokenType::BinaryLiteral(value))
+        } else if self.current_char() == Some('0') && self.peek(1).is_some_and(|c| matches!(c, 'o' | 'O' | '0'..='7')) {
             self.advance(); // skip '0'
-
-            // Read octal digits
-            while let Some(ch) = self.current_char() {
-                if ('0'..='7').contains(&ch) {
-                    self.advance();
-                } else {
-                    break;
-                }
+            if matches!(self.current_char(), Some('o' | 'O')) {
+                self.advance(); // skip the optional 'o'
             }
 
+            let digits = self.read_digits(|c| ('0'..='7').contains(&c))?;
             let num_str: String = self.input[start_pos..self.position].iter().collect();
-            if num_str.len() <= 1 {
+            if digits.is_empty() {
                 return Err(LexerError::new(
                     format!("Invalid octal number: {}", num_str),
                     start_line,
@@ -322,29 +462,53 @@ impl Lexer {
                 ));
             }
 
-            let value = i64::from_str_radix(&num_str[1..], 8)
-                .map_err(|_| LexerError::new(
-                    format!("Invalid octal number: {}", num_str),
-                    start_line,
-                    start_column,
-                    start_pos
-                ))?;
+            let value = parse_integer(&digits, 8, "octal", &num_str, start_line, start_column, start_pos)?;
 
-            return Ok(TokenType::OctalLiteral(value));
+            Ok(TokenType::OctalLiteral(value))
         } else {
-            // Read decimal number (possibly with decimal point)
-            while let Some(ch) = self.current_char() {
-                if ch.is_ascii_digit() || ch == '.' {
-                    self.advance();
-                } else {
+            // Read decimal number (possibly with decimal point); separators are
+            // only allowed between digits, not next to the point. A `..` after
+            // the digits is a range operator, not a decimal point.
+            let mut digits = String::new();
+            loop {
+                digits.push_str(&self.read_digits(|c| c.is_ascii_digit())?);
+                if self.current_char() != Some('.') || self.peek(1) == Some('.') {
                     break;
                 }
+                digits.push('.');
+                self.advance();
+            }
+
+            // Optional exponent: `e` or `E`, an optional sign, then at least one digit.
+            if matches!(self.current_char(), Some('e' | 'E')) {
+                let (exp_line, exp_column, exp_pos) = (self.line, self.column, self.absolute_position);
+                digits.push('e');
+                self.advance();
+                if let Some(sign @ ('+' | '-')) = self.current_char() {
+                    digits.push(sign);
+                    self.advance();
+                }
+                let exponent = self.read_digits(|c| c.is_ascii_digit())?;
+                if exponent.is_empty() {
+                    return Err(LexerError::with_type(LexerErrorType::InvalidNumber, exp_line, exp_column, exp_pos));
+                }
+                digits.push_str(&exponent);
+                if self.current_char() == Some('.') && self.peek(1) != Some('.') {
+                    self.advance();
+                    let num_str: String = self.input[start_pos..self.position].iter().collect();
+                    return Err(LexerError::new(
+                        format!("Invalid float number: {} (exponents must be integers)", num_str),
+                        start_line,
+                        start_column,
+                        start_pos
+                    ));
+                }
             }
 
             let num_str: String = self.input[start_pos..self.position].iter().collect();
 
-            if num_str.contains('.') {
-                let value = num_str.parse::<f64>()
+            if digits.contains(['.', 'e']) {
+                let value = digits.parse::<f64>()
                     .map_err(|_| LexerError::new(
                         format!("Invalid float number: {}", num_str),
                         start_line,
@@ -352,17 +516,11 @@ impl Lexer {
                         start_pos
                     ))?;
 
-                return Ok(TokenType::FloatLiteral(value));
+                Ok(TokenType::FloatLiteral(value))
             } else {
-                let value = num_str.parse::<i64>()
-                    .map_err(|_| LexerError::new(
-                        format!("Invalid integer number: {}", num_str),
-                        start_line,
-                        start_column,
-                        start_pos
-                    ))?;
+                let value = parse_integer(&digits, 10, "integer", &num_str, start_line, start_column, start_pos)?;
 
-                return Ok(TokenType::IntegerLiteral(value));
+                Ok(TokenType::IntegerLiteral(value))
             }
         }
     }
@@ -376,30 +534,14 @@ impl Lexer {
         self.advance(); // skip opening quote
 
         let mut str_value = String::new();
+        let mut terminated = false;
 
         while let Some(ch) = self.current_char() {
             if ch == '\\' {
-                self.advance(); // skip escape character
-                if let Some(escaped_ch) = self.current_char() {
-                    match escaped_ch {
-                        'n' => str_value.push('\n'),
-                        't' => str_value.push('\t'),
-                        'r' => str_value.push('\r'),
-                        '\\' => str_value.push('\\'),
-                        '\'' | '"' => str_value.push(escaped_ch),
-                        _ => str_value.push(escaped_ch),
-                    }
-                    self.advance();
-                } else {
-                    return Err(LexerError::new(
-                        "Unterminated escape sequence in string".to_string(),
-                        self.line,
-                        self.column,
-                        self.absolute_position
-                    ));
-                }
+                str_value.push(self.read_escape()?);
             } else if ch == quote {
                 self.advance(); // skip closing quote
+                terminated = true;
                 break;
             } else {
                 str_value.push(ch);
@@ -407,7 +549,7 @@ impl Lexer {
             }
         }
 
-        if self.current_char() != Some(quote) {
+        if !terminated {
             return Err(LexerError::with_type(
                 LexerErrorType::UnterminatedString,
                 start_line,
@@ -416,112 +558,365 @@ impl Lexer {
             ));
         }
 
-        Ok(TokenType::StringLiteral(str_value))
+        if quote == '"' {
+            return Ok(TokenType::StringLiteral(str_value));
+        }
+        // Single quotes delimit a character literal: exactly one character after escapes.
+        let mut chars = str_value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(TokenType::CharLiteral(ch)),
+            (None, _) => Err(LexerError::new(
+                "Empty character literal".to_string(),
+                start_line,
+                start_column,
+                start_pos
+            )),
+            (Some(_), Some(_)) => Err(LexerError::new(
+                format!("Character literal '{}' has more than one character; use double quotes for strings", str_value),
+                start_line,
+                start_column,
+                start_pos
+            )),
+        }
+    }
+
+    /// Reads a `b"..."` literal into its UTF-8 bytes. Escapes work as in strings,
+    /// except `\u{...}`, which has no place in a sequence of bytes.
+    fn read_byte_string(&mut self) -> Result<TokenType, LexerError> {
+        let start_line = self.line;
+        let start_column = self.column;
+        let start_pos = self.absolute_position;
+
+        self.advance(); // skip 'b'
+        self.advance(); // skip opening quote
+
+        let mut bytes = Vec::new();
+        let mut buf = [0u8; 4];
+        while let Some(ch) = self.current_char() {
+            let ch = match ch {
+                '"' => {
+                    self.advance(); // skip closing quote
+                    return Ok(TokenType::ByteStringLiteral(bytes));
+                }
+                '\\' if self.peek(1) == Some('u') => {
+                    return Err(LexerError::new(
+                        "Unicode escape `\\u{...}` is not allowed in a byte string".to_string(),
+                        self.line,
+                        self.column,
+                        self.absolute_position
+                    ));
+                }
+                '\\' => self.read_escape()?,
+                _ => {
+                    self.advance();
+                    ch
+                }
+            };
+            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+        }
+
+        Err(LexerError::with_type(
+            LexerErrorType::UnterminatedString,
+            start_line,
+            start_column,
+            start_pos
+        ))
     }
 
-    fn read_identifier(&mut self) -> String {
+    /// Decodes the escape sequence starting at the current backslash and leaves
+    /// the cursor just past it. Besides the single-character escapes this handles
+    /// `\xNN` (ASCII only) and `\u{N..}` (any Unicode scalar value).
+    fn read_escape(&mut self) -> Result<char, LexerError> {
+        let start_line = self.line;
+        let start_column = self.column;
+        let start_pos = self.absolute_position;
+        let invalid = || LexerError::with_type(
+            LexerErrorType::InvalidCharacter,
+            start_line,
+            start_column,
+            start_pos
+        );
+
+        self.advance(); // skip escape character
+        let escaped_ch = match self.current_char() {
+            Some(ch) => ch,
+            None => {
+                return Err(LexerError::new(
+                    "Unterminated escape sequence in string".to_string(),
+                    self.line,
+                    self.column,
+                    self.absolute_position
+                ));
+            }
+        };
+        self.advance();
+
+        match escaped_ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            'x' => {
+                let mut digits = String::new();
+                for _ in 0..2 {
+                    match self.current_char() {
+                        Some(ch) if ch.is_ascii_hexdigit() => {
+                            digits.push(ch);
+                            self.advance();
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                let value = u32::from_str_radix(&digits, 16).map_err(|_| invalid())?;
+                // `\x` only spells ASCII; anything wider must use `\u{...}`.
+                if value > 0x7F {
+                    return Err(invalid());
+                }
+                char::from_u32(value).ok_or_else(invalid)
+            }
+            'u' => {
+                if self.current_char() != Some('{') {
+                    return Err(invalid());
+                }
+                self.advance(); // skip '{'
+                let mut digits = String::new();
+                while let Some(ch) = self.current_char() {
+                    if ch == '}' {
+                        break;
+                    }
+                    if !ch.is_ascii_hexdigit() || digits.len() == 6 {
+                        return Err(invalid());
+                    }
+                    digits.push(ch);
+                    self.advance();
+                }
+                if self.current_char() != Some('}') || digits.is_empty() {
+                    return Err(invalid());
+                }
+                self.advance(); // skip '}'
+                // Rejects surrogates (D800-DFFF) and anything above 10FFFF.
+                let value = u32::from_str_radix(&digits, 16).map_err(|_| invalid())?;
+                char::from_u32(value).ok_or_else(invalid)
+            }
+            _ => Ok(escaped_ch),
+        }
+    }
+
+    fn read_identifier(&mut self) -> Result<String, LexerError> {
         let start_pos = self.position;
         
         while let Some(ch) = self.current_char() {
-            if ch.is_alphanumeric() || ch == '_' {
-                self.advance();
-            } else {
+            if !is_ident_continue(ch) {
                 break;
             }
+            if self.ascii_identifiers_only && !ch.is_ascii() {
+                return Err(LexerError::new(
+                    format!("Non-ASCII character in identifier: {}", ch),
+                    self.line,
+                    self.column,
+                    self.absolute_position,
+                ));
+            }
+            self.advance();
         }
         
-        self.input[start_pos..self.position].iter().collect()
+        Ok(self.input[start_pos..self.position].iter().collect())
+    }
+
+    /// Whether the `'` at the cursor starts a label such as `'outer` rather than
+    /// a character literal such as `'a'`, which is closed by a second quote.
+    fn at_label(&self) -> bool {
+        if !self.peek(1).is_some_and(is_ident_start) { return false; }
+        let len = (1..).take_while(|&i| self.peek(i).is_some_and(is_ident_continue)).count();
+        self.peek(1 + len) != Some('\'')
     }
 
     fn lookup_keyword(&self, identifier: &str) -> TokenType {
-        match identifier {
-            "let" => TokenType::Let,
-            "var" => TokenType::Var,
-            "const" => TokenType::Const,
-            "fn" => TokenType::Fn,
-            "struct" => TokenType::Struct,
-            "enum" => TokenType::Enum,
-            "if" => TokenType::If,
-            "else" => TokenType::Else,
-            "while" => TokenType::While,
-            "for" => TokenType::For,
-            "return" => TokenType::Return,
-            "pub" => TokenType::Pub,
-            "priv" => TokenType::Priv,
-            "static" => TokenType::Static,
-            "int" => TokenType::Int,
-            "float" => TokenType::Float,
-            "bool" => TokenType::Bool,
-            "string" => TokenType::String,
-            "char" => TokenType::Char,
-            "void" => TokenType::Void,
-            "true" => TokenType::BooleanLiteral(true),
-            "false" => TokenType::BooleanLiteral(false),
-            _ => TokenType::Identifier(identifier.to_string()),
-        }
+        keyword_token(identifier).unwrap_or_else(|| TokenType::Identifier(identifier.to_string()))
     }
 
     pub fn next_token(&mut self) -> Result<Token, LexerError> {
-        self.skip_whitespace();
-        self.skip_comment();
-        self.skip_whitespace();
+        if !self.preserve_trivia {
+            self.skip_whitespace();
+            while self.current_char() == Some('/') && matches!(self.peek(1), Some('/' | '*')) {
+                self.skip_comment()?;
+                self.skip_whitespace();
+            }
+        }
+
+        let (start, start_pos, line, column) = (self.byte_offset, self.position, self.line, self.column);
+        let mut token = match self.scan_trivia()? {
+            Some(token_type) => Token { token_type, value: String::new(), line, column, span: 0..0, end_line: line, end_column: column },
+            None => self.scan_token()?,
+        };
+        token.span = start..self.byte_offset;
+        (token.end_line, token.end_column) = (self.line, self.column);
+        if self.preserve_trivia {
+            token.value = self.input[start_pos..self.position].iter().collect();
+        }
+        Ok(token)
+    }
 
+    /// In trivia-preserving mode, reads the run of whitespace or the comment at the cursor.
+    fn scan_trivia(&mut self) -> Result<Option<TokenType>, LexerError> {
+        if !self.preserve_trivia { return Ok(None); }
+        let start_pos = self.position;
+        let text = |lexer: &Self| lexer.input[start_pos..lexer.position].iter().collect::<String>();
+        match (self.current_char(), self.peek(1)) {
+            (Some(ch), _) if ch.is_whitespace() => {
+                while self.current_char().is_some_and(char::is_whitespace) { self.advance(); }
+                Ok(Some(TokenType::Whitespace(text(self))))
+            }
+            (Some('/'), Some('/')) => {
+                self.skip_comment()?;
+                Ok(Some(TokenType::LineComment(text(self))))
+            }
+            (Some('/'), Some('*')) => {
+                self.skip_comment()?;
+                Ok(Some(TokenType::BlockComment(text(self))))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads the token at the cursor; its `span` is filled in by `next_token`.
+    fn scan_token(&mut self) -> Result<Token, LexerError> {
+        // Tokens are positioned at their first character, before any of it is consumed.
+        let (line, column) = (self.line, self.column);
         if let Some(current_char) = self.current_char() {
             let token = match current_char {
                 // Single character tokens
                 '(' => Token {
                     token_type: TokenType::LeftParen,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
                 ')' => Token {
                     token_type: TokenType::RightParen,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
                 '{' => Token {
                     token_type: TokenType::LeftBrace,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
                 '}' => Token {
                     token_type: TokenType::RightBrace,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
                 '[' => Token {
                     token_type: TokenType::LeftBracket,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
                 ']' => Token {
                     token_type: TokenType::RightBracket,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
                 ';' => Token {
                     token_type: TokenType::Semicolon,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
                 ',' => Token {
                     token_type: TokenType::Comma,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
                 },
-                '.' => Token {
-                    token_type: TokenType::Dot,
-                    value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                '.' => {
+                    if self.peek(1) == Some('.') && self.peek(2) == Some('=') {
+                        self.advance(); // consume second '.'
+                        self.advance(); // consume '='
+                        Token {
+                            token_type: TokenType::RangeInclusive,
+                            value: "..=".to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    } else if self.peek(1) == Some('.') {
+                        self.advance(); // consume second '.'
+                        Token {
+                            token_type: TokenType::Range,
+                            value: "..".to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    } else {
+                        Token {
+                            token_type: TokenType::Dot,
+                            value: current_char.to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    }
+                },
+                '?' => {
+                    if self.peek(1) == Some('?') {
+                        self.advance(); // consume second '?'
+                        Token {
+                            token_type: TokenType::NullCoalesce,
+                            value: "??".to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    } else {
+                        Token {
+                            token_type: TokenType::Question,
+                            value: current_char.to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    }
                 },
                 ':' => {
                     if self.peek(1) == Some(':') {
@@ -529,15 +924,21 @@ impl Lexer {
                         Token {
                             token_type: TokenType::DoubleColon,
                             value: "::".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::Colon,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -547,23 +948,32 @@ impl Lexer {
                         Token {
                             token_type: TokenType::Arrow,
                             value: "->".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else if self.peek(1) == Some('=') {
                         self.advance(); // consume '='
                         Token {
                             token_type: TokenType::MinusAssign,
                             value: "-=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::Minus,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -573,33 +983,68 @@ impl Lexer {
                         Token {
                             token_type: TokenType::PlusAssign,
                             value: "+=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::Plus,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
                 '*' => {
-                    if self.peek(1) == Some('=') {
+                    if self.peek(1) == Some('*') && self.peek(2) == Some('=') {
+                        self.advance(); // consume second '*'
+                        self.advance(); // consume '='
+                        Token {
+                            token_type: TokenType::PowerAssign,
+                            value: "**=".to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    } else if self.peek(1) == Some('*') {
+                        self.advance(); // consume second '*'
+                        Token {
+                            token_type: TokenType::Power,
+                            value: "**".to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    } else if self.peek(1) == Some('=') {
                         self.advance(); // consume '='
                         Token {
                             token_type: TokenType::MultiplyAssign,
                             value: "*=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::Multiply,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -609,15 +1054,21 @@ impl Lexer {
                         Token {
                             token_type: TokenType::DivideAssign,
                             value: "/=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::Divide,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -627,15 +1078,21 @@ impl Lexer {
                         Token {
                             token_type: TokenType::ModuloAssign,
                             value: "%=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::Modulo,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -645,15 +1102,21 @@ impl Lexer {
                         Token {
                             token_type: TokenType::NotEqual,
                             value: "!=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::LogicalNot,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -663,15 +1126,32 @@ impl Lexer {
                         Token {
                             token_type: TokenType::Equal,
                             value: "==".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
+                        }
+                    } else if self.peek(1) == Some('>') {
+                        self.advance(); // consume '>'
+                        Token {
+                            token_type: TokenType::FatArrow,
+                            value: "=>".to_string(),
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::Assign,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -681,8 +1161,11 @@ impl Lexer {
                         Token {
                             token_type: TokenType::LessEqual,
                             value: "<=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else if self.peek(1) == Some('<') {
                         self.advance(); // consume '<'
@@ -691,23 +1174,32 @@ impl Lexer {
                             Token {
                                 token_type: TokenType::LeftShift,
                                 value: "<<=".to_string(),
-                                line: self.line,
-                                column: self.column,
+                                line,
+                                column,
+                                span: 0..0,
+                                end_line: line,
+                                end_column: column,
                             }
                         } else {
                             Token {
                                 token_type: TokenType::LeftShift,
                                 value: "<<".to_string(),
-                                line: self.line,
-                                column: self.column,
+                                line,
+                                column,
+                                span: 0..0,
+                                end_line: line,
+                                end_column: column,
                             }
                         }
                     } else {
                         Token {
                             token_type: TokenType::LessThan,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -717,8 +1209,11 @@ impl Lexer {
                         Token {
                             token_type: TokenType::GreaterEqual,
                             value: ">=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else if self.peek(1) == Some('>') {
                         self.advance(); // consume '>'
@@ -727,23 +1222,32 @@ impl Lexer {
                             Token {
                                 token_type: TokenType::RightShift,
                                 value: ">>=".to_string(),
-                                line: self.line,
-                                column: self.column,
+                                line,
+                                column,
+                                span: 0..0,
+                                end_line: line,
+                                end_column: column,
                             }
                         } else {
                             Token {
                                 token_type: TokenType::RightShift,
                                 value: ">>".to_string(),
-                                line: self.line,
-                                column: self.column,
+                                line,
+                                column,
+                                span: 0..0,
+                                end_line: line,
+                                end_column: column,
                             }
                         }
                     } else {
                         Token {
                             token_type: TokenType::GreaterThan,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -753,23 +1257,32 @@ impl Lexer {
                         Token {
                             token_type: TokenType::LogicalAnd,
                             value: "&&".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else if self.peek(1) == Some('=') {
                         self.advance(); // consume '='
                         Token {
                             token_type: TokenType::BitwiseAnd,
                             value: "&=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::BitwiseAnd,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -779,23 +1292,32 @@ impl Lexer {
                         Token {
                             token_type: TokenType::LogicalOr,
                             value: "||".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else if self.peek(1) == Some('=') {
                         self.advance(); // consume '='
                         Token {
                             token_type: TokenType::BitwiseOr,
                             value: "|=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::BitwiseOr,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
@@ -805,68 +1327,125 @@ impl Lexer {
                         Token {
                             token_type: TokenType::BitwiseXor,
                             value: "^=".to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     } else {
                         Token {
                             token_type: TokenType::BitwiseXor,
                             value: current_char.to_string(),
-                            line: self.line,
-                            column: self.column,
+                            line,
+                            column,
+                            span: 0..0,
+                            end_line: line,
+                            end_column: column,
                         }
                     }
                 },
                 '~' => Token {
                     token_type: TokenType::BitwiseNot,
                     value: current_char.to_string(),
-                    line: self.line,
-                    column: self.column,
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
+                },
+                '@' => Token {
+                    token_type: TokenType::At,
+                    value: current_char.to_string(),
+                    line,
+                    column,
+                    span: 0..0,
+                    end_line: line,
+                    end_column: column,
+                },
+                '\'' if self.at_label() => {
+                    self.advance(); // skip '\''
+                    let name = self.read_identifier()?;
+                    return Ok(Token {
+                        token_type: TokenType::Label(name.clone()),
+                        value: name,
+                        line,
+                        column,
+                        span: 0..0,
+                        end_line: line,
+                        end_column: column,
+                    });
+                },
+                'b' if self.peek(1) == Some('"') => {
+                    let token_type = self.read_byte_string()?;
+                    let value = match &token_type {
+                        TokenType::ByteStringLiteral(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                        _ => "".to_string(),
+                    };
+                    return Ok(Token {
+                        token_type,
+                        value,
+                        line,
+                        column,
+                        span: 0..0,
+                        end_line: line,
+                        end_column: column,
+                    });
                 },
                 '"' | '\'' => {
                     let token_type = self.read_string()?;
-                    let value = if let TokenType::StringLiteral(s) = &token_type {
-                        s.clone()
-                    } else {
-                        "".to_string()
+                    let value = match &token_type {
+                        TokenType::StringLiteral(s) => s.clone(),
+                        TokenType::CharLiteral(c) => c.to_string(),
+                        _ => "".to_string(),
                     };
-                    Token {
+                    return Ok(Token {
                         token_type,
                         value,
-                        line: self.line,
-                        column: self.column,
-                    }
+                        line,
+                        column,
+                        span: 0..0,
+                        end_line: line,
+                        end_column: column,
+                    });
                 },
                 c if c.is_ascii_digit() => {
                     let token_type = self.read_number()?;
                     let value = match &token_type {
                         TokenType::IntegerLiteral(v) => v.to_string(),
-                        TokenType::FloatLiteral(v) => v.to_string(),
+                        // `Debug` keeps the point in whole numbers: `20.0`, not `20`.
+                        TokenType::FloatLiteral(v) => format!("{:?}", v),
                         TokenType::HexLiteral(v) => format!("0x{:x}", v),
                         TokenType::BinaryLiteral(v) => format!("0b{:b}", v),
                         TokenType::OctalLiteral(v) => format!("0o{:o}", v),
                         _ => "".to_string(),
                     };
-                    Token {
+                    return Ok(Token {
                         token_type,
                         value,
-                        line: self.line,
-                        column: self.column,
-                    }
+                        line,
+                        column,
+                        span: 0..0,
+                        end_line: line,
+                        end_column: column,
+                    });
                 },
-                c if c.is_alphabetic() || c == '_' => {
-                    let identifier = self.read_identifier();
+                c if is_ident_start(c) => {
+                    let identifier = self.read_identifier()?;
                     let token_type = self.lookup_keyword(&identifier);
                     let value = match &token_type {
                         TokenType::Identifier(s) => s.clone(),
                         _ => identifier,
                     };
-                    Token {
+                    return Ok(Token {
                         token_type,
                         value,
-                        line: self.line,
-                        column: self.column,
-                    }
+                        line,
+                        column,
+                        span: 0..0,
+                        end_line: line,
+                        end_column: column,
+                    });
                 },
                 _ => {
                     return Err(LexerError::new(
@@ -878,33 +1457,87 @@ impl Lexer {
                 }
             };
 
+            // Operator and punctuation arms leave the cursor on their last character.
+            self.advance();
             Ok(token)
         } else {
             Ok(Token {
                 token_type: TokenType::Eof,
                 value: "".to_string(),
-                line: self.line,
-                column: self.column,
+                line,
+                column,
+                span: 0..0,
+                end_line: line,
+                end_column: column,
             })
         }
     }
 
     pub fn tokenize(&mut self) -> Result<Vec<Token>, LexerError> {
-        let mut tokens = Vec::new();
+        self.iter().collect()
+    }
 
+    /// Like `tokenize`, but keeps going after an error by skipping the
+    /// character it was reported at, so every error in the input is returned.
+    /// The tokens always end with `Eof`.
+    pub fn tokenize_collect(&mut self) -> (Vec<Token>, Vec<LexerError>) {
+        let (mut tokens, mut errors) = (Vec::new(), Vec::new());
         loop {
-            let token = self.next_token()?;
-            tokens.push(token.clone());
-            
-            if matches!(token.token_type, TokenType::Eof) {
-                break;
+            match self.next_token() {
+                Ok(token) => {
+                    let eof = token.token_type == TokenType::Eof;
+                    tokens.push(token);
+                    if eof { break; }
+                }
+                Err(error) => {
+                    while self.absolute_position <= error.position && self.current_char().is_some() {
+                        self.advance();
+                    }
+                    errors.push(error);
+                }
             }
         }
+        (tokens, errors)
+    }
+
+    /// Streams tokens one `next_token` call at a time instead of collecting them.
+    pub fn iter(&mut self) -> Tokens<'_> {
+        Tokens { lexer: self, done: false }
+    }
+}
+
+/// Iterator returned by [`Lexer::iter`]. Yields tokens up to and including
+/// `Eof`, or up to the first error, and then `None`.
+pub struct Tokens<'a> {
+    lexer: &'a mut Lexer,
+    done: bool,
+}
 
-        Ok(tokens)
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+        let result = self.lexer.next_token();
+        self.done = !matches!(&result, Ok(token) if token.token_type != TokenType::Eof);
+        Some(result)
     }
 }
 
+/// Finds the token whose byte span contains `offset`, e.g. the token under an
+/// editor cursor. `tokens` must be in source order, as returned by `tokenize`.
+pub fn token_at(tokens: &[Token], offset: usize) -> Option<&Token> {
+    let index = tokens.partition_point(|t| t.span.end <= offset);
+    tokens.get(index).filter(|t| t.span.contains(&offset))
+}
+
+/// Converts a 1-based column counted in chars, as on `Token`, into the 1-based
+/// column in UTF-16 code units that LSP clients expect. Characters outside the
+/// Basic Multilingual Plane, such as most emoji, take two UTF-16 units.
+pub fn utf16_column(line: &str, column: usize) -> usize {
+    1 + line.chars().take(column.saturating_sub(1)).map(char::len_utf16).sum::<usize>()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -915,12 +1548,174 @@ mod tests {
         let mut lexer = Lexer::new(input);
         let tokens = lexer.tokenize().expect("Failed to tokenize");
 
-        assert_eq!(tokens.len(), 5); // let, x, =, 42, ;
+        assert_eq!(tokens.len(), 6); // let, x, =, 42, ;, EOF
         assert_eq!(tokens[0].token_type, TokenType::Let);
         assert_eq!(tokens[1].token_type, TokenType::Identifier("x".to_string()));
         assert_eq!(tokens[2].token_type, TokenType::Assign);
         assert_eq!(tokens[3].token_type, TokenType::IntegerLiteral(42));
         assert_eq!(tokens[4].token_type, TokenType::Semicolon);
+        assert_eq!(tokens[5].token_type, TokenType::Eof);
+    }
+
+    #[test]
+    fn test_power_operators() {
+        let types = |input: &str| -> Vec<TokenType> {
+            Lexer::new(input).tokenize().expect("Failed to tokenize").into_iter().map(|t| t.token_type).collect()
+        };
+        let (a, b) = (TokenType::Identifier("a".to_string()), TokenType::Identifier("b".to_string()));
+        assert_eq!(types("a ** b"), vec![a.clone(), TokenType::Power, b.clone(), TokenType::Eof]);
+        assert_eq!(types("a **= b"), vec![a.clone(), TokenType::PowerAssign, b.clone(), TokenType::Eof]);
+        assert_eq!(types("a *= b"), vec![a.clone(), TokenType::MultiplyAssign, b.clone(), TokenType::Eof]);
+        assert_eq!(types("a *** b"), vec![a, TokenType::Power, TokenType::Multiply, b, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_null_coalescing_operator() {
+        let types = |input: &str| -> Vec<TokenType> {
+            Lexer::new(input).tokenize().expect("Failed to tokenize").into_iter().map(|t| t.token_type).collect()
+        };
+        let x = TokenType::Identifier("x".to_string());
+        assert_eq!(types("x ?? 0"), vec![x.clone(), TokenType::NullCoalesce, TokenType::IntegerLiteral(0), TokenType::Eof]);
+        assert_eq!(types("int?"), vec![TokenType::Int, TokenType::Question, TokenType::Eof]);
+        assert_eq!(types("x???"), vec![x, TokenType::NullCoalesce, TokenType::Question, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_range_operators() {
+        let types = |input: &str| -> Vec<TokenType> {
+            Lexer::new(input).tokenize().expect("Failed to tokenize").into_iter().map(|t| t.token_type).collect()
+        };
+        let (zero, nine, ten) = (TokenType::IntegerLiteral(0), TokenType::IntegerLiteral(9), TokenType::IntegerLiteral(10));
+        assert_eq!(types("0..10"), vec![zero.clone(), TokenType::Range, ten, TokenType::Eof]);
+        assert_eq!(types("0..=9"), vec![zero, TokenType::RangeInclusive, nine, TokenType::Eof]);
+        assert_eq!(types("1.5"), vec![TokenType::FloatLiteral(1.5), TokenType::Eof]);
+        let x = TokenType::Identifier("x".to_string());
+        assert_eq!(types("p.x"), vec![TokenType::Identifier("p".to_string()), TokenType::Dot, x.clone(), TokenType::Eof]);
+        assert_eq!(types("x..x"), vec![x.clone(), TokenType::Range, x, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_byte_strings() {
+        let tokens = Lexer::new("b\"hi\\n\" buffer b").tokenize().expect("Failed to tokenize");
+        assert_eq!(tokens[0].token_type, TokenType::ByteStringLiteral(b"hi\n".to_vec()));
+        assert_eq!(tokens[1].token_type, TokenType::Identifier("buffer".to_string()));
+        assert_eq!(tokens[2].token_type, TokenType::Identifier("b".to_string()));
+
+        let err = Lexer::new("b\"\\u{48}\"").tokenize().unwrap_err();
+        assert!(err.message.contains("not allowed in a byte string"));
+        assert_eq!(err.column, 3);
+    }
+
+    #[test]
+    fn test_as_keyword() {
+        let mut lexer = Lexer::new("x as int");
+        let tokens = lexer.tokenize().expect("Failed to tokenize");
+
+        assert_eq!(tokens[0].token_type, TokenType::Identifier("x".to_string()));
+        assert_eq!(tokens[1].token_type, TokenType::As);
+        assert_eq!(tokens[2].token_type, TokenType::Int);
+        assert_eq!(tokens[3].token_type, TokenType::Eof);
+    }
+
+    #[test]
+    fn test_keyword_token() {
+        assert_eq!(keyword_token("while"), Some(TokenType::While));
+        assert_eq!(keyword_token("true"), Some(TokenType::BooleanLiteral(true)));
+        assert_eq!(keyword_token("foo"), None);
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let tokens = Lexer::new("café").tokenize().expect("Failed to tokenize");
+        assert_eq!(tokens[0].token_type, TokenType::Identifier("café".to_string()));
+
+        let err = Lexer::new("café").ascii_identifiers_only(true).tokenize().unwrap_err();
+        assert_eq!(err.column, 4);
+        assert!(Lexer::new("cafe_2").ascii_identifiers_only(true).tokenize().is_ok());
+    }
+
+    #[test]
+    fn test_token_at_offset() {
+        // Byte offsets: `let` 0..3, `café` 4..9, `=` 10..11, `42` 12..14, `;` 14..15.
+        let tokens = Lexer::new("let café = 42;").tokenize().expect("Failed to tokenize");
+        assert_eq!(tokens[1].span, 4..9);
+
+        assert_eq!(token_at(&tokens, 0).unwrap().token_type, TokenType::Let);
+        assert_eq!(token_at(&tokens, 2).unwrap().token_type, TokenType::Let);
+        assert_eq!(token_at(&tokens, 8).unwrap().token_type, TokenType::Identifier("café".to_string()));
+        assert_eq!(token_at(&tokens, 13).unwrap().token_type, TokenType::IntegerLiteral(42));
+        assert_eq!(token_at(&tokens, 14).unwrap().token_type, TokenType::Semicolon);
+        // Whitespace between tokens and the end of input are not covered by any token.
+        assert!(token_at(&tokens, 3).is_none());
+        assert!(token_at(&tokens, 9).is_none());
+        assert!(token_at(&tokens, 15).is_none());
+    }
+
+    #[test]
+    fn test_utf16_column_after_emoji() {
+        let line = "s = \"😀\";";
+        let tokens = Lexer::new(line).tokenize().expect("Failed to tokenize");
+        let semicolon = tokens.iter().find(|t| t.token_type == TokenType::Semicolon).unwrap();
+        assert_eq!(semicolon.column, 8);
+        assert_eq!(utf16_column(line, semicolon.column), 9);
+        // Columns before the emoji are the same in both encodings.
+        assert_eq!(utf16_column(line, 3), 3);
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        let lex = |src: &str| Lexer::new(src).tokenize().expect("Failed to tokenize")[0].token_type.clone();
+        assert_eq!(lex("1_000"), TokenType::IntegerLiteral(1000));
+        assert_eq!(lex("1_000_000"), TokenType::IntegerLiteral(1_000_000));
+        assert_eq!(lex("1_000.000_5"), TokenType::FloatLiteral(1000.0005));
+        assert_eq!(lex("0xFF_FF"), TokenType::HexLiteral(0xFFFF));
+        assert_eq!(lex("0b1010_0101"), TokenType::BinaryLiteral(0b1010_0101));
+        assert_eq!(lex("017_7"), TokenType::OctalLiteral(0o177));
+        // A name can start with `_`, so `_100` is an identifier rather than a number.
+        assert_eq!(lex("_100"), TokenType::Identifier("_100".to_string()));
+    }
+
+    #[test]
+    fn test_scientific_notation() {
+        let lex = |src: &str| Lexer::new(src).tokenize().expect("Failed to tokenize")[0].token_type.clone();
+        assert_eq!(lex("1e10"), TokenType::FloatLiteral(1e10));
+        assert_eq!(lex("2.5e-3"), TokenType::FloatLiteral(2.5e-3));
+        assert_eq!(lex("6.022e23"), TokenType::FloatLiteral(6.022e23));
+        assert_eq!(lex("1E+2"), TokenType::FloatLiteral(100.0));
+
+        let err = Lexer::new("1e").tokenize().unwrap_err();
+        assert_eq!(err.message, "Invalid number format");
+        assert_eq!(err.column, 2);
+        let err = Lexer::new("1.5e3.2").tokenize().unwrap_err();
+        assert!(err.message.starts_with("Invalid float number: 1.5e3."));
+        assert_eq!(err.column, 1);
+    }
+
+    #[test]
+    fn test_integer_literal_out_of_range() {
+        for input in ["x = 99999999999999999999;", "x = 0xFFFFFFFFFFFFFFFFF;"] {
+            let err = Lexer::new(input).tokenize().unwrap_err();
+            assert!(err.message.starts_with("integer literal out of range for 64-bit signed integer"), "{}", err.message);
+            assert_eq!((err.line, err.column), (1, 5));
+        }
+        assert!(Lexer::new("9223372036854775807").tokenize().is_ok());
+    }
+
+    #[test]
+    fn test_misplaced_digit_separators() {
+        // (source, column of the offending `_`)
+        for (src, column) in [("100_", 4), ("1__0", 2), ("0x_FF", 3), ("0b1_", 4), ("1_.5", 2), ("1._5", 3)] {
+            let err = Lexer::new(src).tokenize().unwrap_err();
+            assert_eq!(err.message, "Invalid number format", "{}", src);
+            assert_eq!((err.line, err.column), (1, column), "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let err = Lexer::new("let x = 1; /* never closed").tokenize().unwrap_err();
+        assert_eq!(err.message, "Unexpected end of file");
+        assert_eq!((err.line, err.column), (1, 12));
     }
 
     #[test]
@@ -937,6 +1732,42 @@ mod tests {
         assert_eq!(tokens[5].token_type, TokenType::Struct);
     }
 
+    #[test]
+    fn test_token_end_positions() {
+        let tokens = Lexer::new("let größe = \"a\nbc\";").tokenize().expect("Failed to tokenize");
+        let name = &tokens[1];
+        assert_eq!(name.end_line, name.line);
+        assert_eq!(name.end_column - name.column, "größe".chars().count());
+        let string = &tokens[3];
+        assert_eq!((string.line, string.column), (1, 13));
+        assert_eq!((string.end_line, string.end_column), (2, 4));
+    }
+
+    #[test]
+    fn test_tokenize_collect_reports_every_error() {
+        let (tokens, errors) = Lexer::new("let a = 1 $ 2;\nlet b = #;").tokenize_collect();
+        let positions: Vec<(usize, usize)> = errors.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(positions, vec![(1, 11), (2, 9)]);
+        assert!(errors[1].message.contains('#'));
+        let types: Vec<TokenType> = tokens.into_iter().map(|t| t.token_type).collect();
+        assert_eq!(types[3..6], [TokenType::IntegerLiteral(1), TokenType::IntegerLiteral(2), TokenType::Semicolon]);
+        assert_eq!(types.last(), Some(&TokenType::Eof));
+    }
+
+    #[test]
+    fn test_control_flow_keywords() {
+        let tokens = Lexer::new("match break continue loop import").tokenize().expect("Failed to tokenize");
+        let types: Vec<TokenType> = tokens.into_iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![
+            TokenType::Match,
+            TokenType::Break,
+            TokenType::Continue,
+            TokenType::Loop,
+            TokenType::Import,
+            TokenType::Eof,
+        ]);
+    }
+
     #[test]
     fn test_operators() {
         let input = "== != <= >= && || ! & | ^ ~ << >> += -= *= /= %= ->";
@@ -963,4 +1794,105 @@ mod tests {
         assert_eq!(tokens[17].token_type, TokenType::ModuloAssign);
         assert_eq!(tokens[18].token_type, TokenType::Arrow);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_iter_streams_until_eof_or_error() {
+        let mut lexer = Lexer::new("let x;");
+        let types: Vec<TokenType> = lexer.iter().map(|t| t.unwrap().token_type).collect();
+        assert_eq!(types.len(), 4);
+        assert_eq!(types[3], TokenType::Eof);
+
+        let mut lexer = Lexer::new("a $ b");
+        let mut tokens = lexer.iter();
+        assert!(tokens.next().unwrap().is_ok());
+        assert!(tokens.next().unwrap().is_err());
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn test_trivia_round_trips_source() {
+        let source = "// header\nfn main() {\n\tlet s = \"a\\tb\"; /* inline */ let n = 0x1F;\n}\n";
+        let tokens = Lexer::new(source).preserve_trivia(true).tokenize().expect("Failed to tokenize");
+        let rebuilt: String = tokens.iter().map(|t| t.value.as_str()).collect();
+        assert_eq!(rebuilt, source);
+        assert_eq!(tokens[0].token_type, TokenType::LineComment("// header".to_string()));
+        assert_eq!(tokens[1].token_type, TokenType::Whitespace("\n".to_string()));
+        assert!(tokens.iter().any(|t| t.token_type == TokenType::BlockComment("/* inline */".to_string())));
+        assert!(tokens.iter().all(|t| source[t.span.clone()] == t.value));
+    }
+
+    #[test]
+    fn test_trivia_is_skipped_by_default() {
+        let tokens = Lexer::new("let /* c */ x; // end").tokenize().expect("Failed to tokenize");
+        let types: Vec<TokenType> = tokens.into_iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::Let, TokenType::Identifier("x".to_string()), TokenType::Semicolon, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_tokens_round_trip_through_json() {
+        let tokens = Lexer::new("let s = \"hi\"; x += 0x1F ** 2.5 'a' 'loop: b\"\\x00\"").tokenize().expect("Failed to tokenize");
+        let json = serde_json::to_string(&tokens).unwrap();
+        let decoded: Vec<Token> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, tokens);
+        let literal = serde_json::to_value(TokenType::IntegerLiteral(42)).unwrap();
+        assert_eq!(literal, serde_json::json!({ "type": "IntegerLiteral", "value": 42 }));
+        assert_eq!(serde_json::to_value(TokenType::Let).unwrap(), serde_json::json!({ "type": "Let" }));
+    }
+
+    #[test]
+    fn test_consecutive_comments_are_skipped() {
+        let tokens = Lexer::new("// one\n// two\n/* three */ 'outer: x => @").tokenize().expect("Failed to tokenize");
+        let types: Vec<TokenType> = tokens.into_iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![
+            TokenType::Label("outer".to_string()),
+            TokenType::Colon,
+            TokenType::Identifier("x".to_string()),
+            TokenType::FatArrow,
+            TokenType::At,
+            TokenType::Eof,
+        ]);
+    }
+
+    #[test]
+    fn test_tokens_start_at_first_character() {
+        let tokens = Lexer::new("a == b -> c").tokenize().expect("Failed to tokenize");
+        assert_eq!((tokens[0].value.as_str(), tokens[0].column), ("a", 1));
+        assert_eq!((tokens[1].value.as_str(), tokens[1].column), ("==", 3));
+        assert_eq!((tokens[2].value.as_str(), tokens[2].column), ("b", 6));
+        assert_eq!((tokens[3].value.as_str(), tokens[3].column), ("->", 8));
+    }
+
+    #[test]
+    fn test_hex_and_unicode_escapes() {
+        let input = r#""\x41" '\u{1F600}'"#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize().expect("Failed to tokenize");
+
+        assert_eq!(tokens[0].token_type, TokenType::StringLiteral("A".to_string()));
+        assert_eq!(tokens[1].token_type, TokenType::CharLiteral('\u{1F600}'));
+    }
+
+    #[test]
+    fn test_char_literals() {
+        let tokens = Lexer::new(r"'a' '\n'").tokenize().expect("Failed to tokenize");
+        assert_eq!(tokens[0].token_type, TokenType::CharLiteral('a'));
+        assert_eq!(tokens[0].value, "a");
+        assert_eq!(tokens[1].token_type, TokenType::CharLiteral('\n'));
+
+        let err = Lexer::new("x = 'ab';").tokenize().unwrap_err();
+        assert!(err.message.contains("more than one character"), "{}", err.message);
+        assert_eq!((err.line, err.column), (1, 5));
+        let err = Lexer::new("''").tokenize().unwrap_err();
+        assert_eq!(err.message, "Empty character literal");
+    }
+
+    #[test]
+    fn test_invalid_escapes() {
+        for input in [r"'\u{D800}'", r"'\u{110000}'", r"'\x80'", r"'\u{}'", r"'\x4'"] {
+            let mut lexer = Lexer::new(input);
+            let err = lexer.tokenize().expect_err(input);
+            assert_eq!(err.message, "Invalid character", "{}", input);
+            assert_eq!((err.line, err.column), (1, 2), "{}", input);
+        }
+    }
+}
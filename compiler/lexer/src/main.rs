@@ -1,41 +1,25 @@
+use fax_lexer::{Lexer, LexerError, Token, TokenType};
 use serde::{Serialize, Deserialize};
 use std::env;
 use std::fs;
 
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-enum TokenType {
-    LET, VAR, CONST, FN, STRUCT, ENUM, IF, ELSE, WHILE, FOR, RETURN, BREAK, CONTINUE, IMPORT,
-    PTR, REF, SELF,
-    INT, FLOAT, BOOL, STRING, VOID, TRUE, FALSE,
-    IDENTIFIER, INTEGER_LITERAL, FLOAT_LITERAL, STRING_LITERAL,
-    PLUS, MINUS, MULTIPLY, DIVIDE, MODULO, ASSIGN, EQUAL, NOT_EQUAL,
-    LESS_THAN, GREATER_THAN, LESS_EQUAL, GREATER_EQUAL,
-    LOGICAL_NOT, LOGICAL_AND, LOGICAL_OR,
-    LEFT_PAREN, RIGHT_PAREN, LEFT_BRACE, RIGHT_BRACE, LEFT_BRACKET, RIGHT_BRACKET,
-    SEMICOLON, COMMA, DOT, COLON, ARROW, AMPERSAND, RANGE,
-    EOF
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 struct Position {
     line: usize,
     column: usize,
 }
 
+/// A token as the parser reads it: `type` is the upper-case name from `type_name`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct Token {
+struct JsonToken {
     #[serde(rename = "type")]
-    token_type: TokenType,
+    token_type: &'static str,
     value: String,
     position: Position,
-}
-
-struct Lexer {
-    input: Vec<char>,
-    pos: usize,
-    line: usize,
-    column: usize,
+    /// Source position of each character of a string literal's decoded value, present
+    /// only when escapes or line breaks make it differ from `column + 1 + index`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    offsets: Option<Vec<Position>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -53,265 +37,223 @@ struct Span {
     label: String,
 }
 
-impl Lexer {
-    fn new(input: String) -> Self {
-        Lexer {
-            input: input.chars().collect(),
-            pos: 0,
-            line: 1,
-            column: 1,
-        }
-    }
-
-    fn report_error(&self, ch: char) -> ! {
-        let diag = Diagnostic {
-            code: "E000".to_string(),
-            message: format!("illegal character: '{}'", ch),
-            primary_span: Span {
-                line: self.line,
-                column: self.column,
-                length: 1,
-                label: "unexpected character here".to_string(),
-            },
-        };
-        eprintln!("{}", serde_json::to_string(&diag).unwrap());
-        std::process::exit(1);
-    }
-
-    fn advance(&mut self) -> Option<char> {
-        if self.pos >= self.input.len() {
-            return None;
-        }
-        let ch = self.input[self.pos];
-        self.pos += 1;
-        if ch == '\n' {
-            self.line += 1;
-            self.column = 1;
-        } else {
-            self.column += 1;
-        }
-        Some(ch)
-    }
-
-    fn peek(&self) -> Option<char> {
-        if self.pos >= self.input.len() {
-            None
-        } else {
-            Some(self.input[self.pos])
-        }
-    }
-
-    fn tokenize(&mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        while let Some(ch) = self.peek() {
-            if ch.is_whitespace() {
-                self.advance();
-            } else if ch == '/' && self.pos + 1 < self.input.len() && self.input[self.pos + 1] == '/' {
-                while let Some(c) = self.advance() {
-                    if c == '\n' { break; }
-                }
-            } else if ch == '/' && self.pos + 1 < self.input.len() && self.input[self.pos + 1] == '*' {
-                self.advance(); // /
-                self.advance(); // *
-                while let Some(c) = self.advance() {
-                    if c == '*' && self.peek() == Some('/') {
-                        self.advance(); // /
-                        break;
-                    }
-                }
-            } else if ch.is_alphabetic() || ch == '_' {
-                tokens.push(self.read_identifier());
-            } else if ch.is_numeric() || (ch == '.' && self.pos + 1 < self.input.len() && self.input[self.pos + 1].is_numeric()) {
-                tokens.push(self.read_number());
-            } else if ch == '"' {
-                tokens.push(self.read_string());
-            } else {
-                if let Some(token) = self.read_punctuation_or_operator() {
-                    tokens.push(token);
-                } else {
-                    let ch = self.peek().unwrap();
-                    self.report_error(ch);
-                }
-            }
-        }
-        tokens.push(Token {
-            token_type: TokenType::EOF,
-            value: "".to_string(),
-            position: Position { line: self.line, column: self.column },
-        });
-        tokens
+/// The name the parser knows `token` by. The library lexes `&=`, `|=`, `^=`,
+/// `<<=` and `>>=` as their operator's type, so those are told apart by value.
+fn type_name(token: &Token) -> &'static str {
+    use TokenType::*;
+    let assign = token.value.ends_with('=');
+    match &token.token_type {
+        Let => "LET",
+        Var => "VAR",
+        Const => "CONST",
+        Fn => "FN",
+        Struct => "STRUCT",
+        Enum => "ENUM",
+        Impl => "IMPL",
+        If => "IF",
+        Else => "ELSE",
+        While => "WHILE",
+        For => "FOR",
+        Return => "RETURN",
+        Match => "MATCH",
+        Break => "BREAK",
+        Continue => "CONTINUE",
+        Import => "IMPORT",
+        // Reserved by the library lexer, but the parser has no grammar for them
+        // yet, so they stay identifiers as they were before.
+        Loop | Pub | Priv | Static => "IDENTIFIER",
+        Ptr => "PTR",
+        Ref => "REF",
+        SelfValue => "SELF",
+        Mut => "MUT",
+        Int => "INT",
+        Float => "FLOAT",
+        Bool => "BOOL",
+        String => "STRING",
+        Char => "CHAR",
+        Void => "VOID",
+        As => "AS",
+        True | BooleanLiteral(true) => "TRUE",
+        False | BooleanLiteral(false) => "FALSE",
+        Null => "NULL",
+        Identifier(_) => "IDENTIFIER",
+        IntegerLiteral(_) | HexLiteral(_) | BinaryLiteral(_) | OctalLiteral(_) => "INTEGER_LITERAL",
+        FloatLiteral(_) => "FLOAT_LITERAL",
+        StringLiteral(_) => "STRING_LITERAL",
+        ByteStringLiteral(_) => "BYTE_STRING_LITERAL",
+        CharLiteral(_) => "CHAR_LITERAL",
+        Plus => "PLUS",
+        Minus => "MINUS",
+        Multiply => "MULTIPLY",
+        Divide => "DIVIDE",
+        Modulo => "MODULO",
+        Power => "POWER",
+        Assign => "ASSIGN",
+        Equal => "EQUAL",
+        NotEqual => "NOT_EQUAL",
+        LessThan => "LESS_THAN",
+        GreaterThan => "GREATER_THAN",
+        LessEqual => "LESS_EQUAL",
+        GreaterEqual => "GREATER_EQUAL",
+        LogicalAnd => "LOGICAL_AND",
+        LogicalOr => "LOGICAL_OR",
+        LogicalNot => "LOGICAL_NOT",
+        BitwiseAnd if assign => "AMPERSAND_ASSIGN",
+        BitwiseAnd => "AMPERSAND",
+        BitwiseOr if assign => "PIPE_ASSIGN",
+        BitwiseOr => "PIPE",
+        BitwiseXor if assign => "CARET_ASSIGN",
+        BitwiseXor => "CARET",
+        BitwiseNot => "TILDE",
+        LeftShift if assign => "LEFT_SHIFT_ASSIGN",
+        LeftShift => "LEFT_SHIFT",
+        RightShift if assign => "RIGHT_SHIFT_ASSIGN",
+        RightShift => "RIGHT_SHIFT",
+        PlusAssign => "PLUS_ASSIGN",
+        MinusAssign => "MINUS_ASSIGN",
+        MultiplyAssign => "MULTIPLY_ASSIGN",
+        DivideAssign => "DIVIDE_ASSIGN",
+        ModuloAssign => "MODULO_ASSIGN",
+        PowerAssign => "POWER_ASSIGN",
+        LeftParen => "LEFT_PAREN",
+        RightParen => "RIGHT_PAREN",
+        LeftBrace => "LEFT_BRACE",
+        RightBrace => "RIGHT_BRACE",
+        LeftBracket => "LEFT_BRACKET",
+        RightBracket => "RIGHT_BRACKET",
+        Semicolon => "SEMICOLON",
+        Comma => "COMMA",
+        Dot => "DOT",
+        Range => "RANGE",
+        RangeInclusive => "RANGE_INCLUSIVE",
+        Question => "QUESTION",
+        NullCoalesce => "NULL_COALESCE",
+        Colon => "COLON",
+        DoubleColon => "DOUBLE_COLON",
+        Arrow => "ARROW",
+        FatArrow => "FAT_ARROW",
+        At => "AT",
+        Label(_) => "LABEL",
+        // Trivia is only produced by `Lexer::preserve_trivia`, which this binary leaves off.
+        LineComment(_) | BlockComment(_) | Whitespace(_) => "TRIVIA",
+        Eof => "EOF",
     }
+}
 
-    fn read_identifier(&mut self) -> Token {
-        let line = self.line;
-        let column = self.column;
-        let mut value = String::new();
-        while let Some(ch) = self.peek() {
-            if ch.is_alphanumeric() || ch == '_' {
-                value.push(self.advance().unwrap());
-            } else { break; }
+/// Source position of each decoded character of the string literal `raw`
+/// (quotes included) that starts at `line:column`. An escape sequence maps
+/// to the position of its backslash.
+fn string_offsets(raw: &str, line: usize, column: usize) -> Vec<Position> {
+    let mut pos = Position { line, column: column + 1 };
+    let step = |pos: &mut Position, ch: char| {
+        if ch == '\n' { (pos.line, pos.column) = (pos.line + 1, 1); } else { pos.column += 1; }
+    };
+    let mut offsets = Vec::new();
+    let mut chars = raw.chars().skip(1);
+    while let Some(ch) = chars.next() {
+        if ch == '"' { break; }
+        offsets.push(pos.clone());
+        step(&mut pos, ch);
+        if ch != '\\' { continue; }
+        let Some(escaped) = chars.next() else { break };
+        step(&mut pos, escaped);
+        // `\xNN` and `\u{N..}` run past the character after the backslash.
+        let mut rest = match escaped { 'x' => 2, 'u' => usize::MAX, _ => 0 };
+        while rest > 0 {
+            let Some(c) = chars.next() else { break };
+            step(&mut pos, c);
+            rest = if c == '}' { 0 } else { rest - 1 };
         }
-        let token_type = match value.as_str() {
-            "let" => TokenType::LET,
-            "const" => TokenType::CONST,
-            "fn" => TokenType::FN,
-            "struct" => TokenType::STRUCT,
-            "if" => TokenType::IF,
-            "else" => TokenType::ELSE,
-            "while" => TokenType::WHILE,
-            "for" => TokenType::FOR,
-            "return" => TokenType::RETURN,
-            "break" => TokenType::BREAK,
-            "continue" => TokenType::CONTINUE,
-            "import" => TokenType::IMPORT,
-            "ptr" => TokenType::PTR,
-            "ref" => TokenType::REF,
-            "self" => TokenType::SELF,
-            "int" => TokenType::INT,
-            "float" => TokenType::FLOAT,
-            "string" => TokenType::STRING,
-            "bool" => TokenType::BOOL,
-            "true" => TokenType::TRUE,
-            "false" => TokenType::FALSE,
-            _ => TokenType::IDENTIFIER,
-        };
-        Token { token_type, value, position: Position { line, column } }
     }
+    offsets
+}
 
-    fn read_number(&mut self) -> Token {
-        let line = self.line;
-        let column = self.column;
-        let mut value = String::new();
-        let mut is_float = false;
-        while let Some(ch) = self.peek() {
-            if ch.is_numeric() {
-                value.push(self.advance().unwrap());
-            } else if ch == '.' && !is_float {
-                // Check if it's a double dot range operator
-                if self.pos + 1 < self.input.len() && self.input[self.pos + 1] == '.' {
-                    break;
-                }
-                is_float = true;
-                value.push(self.advance().unwrap());
-            } else { break; }
-        }
-        Token {
-            token_type: if is_float { TokenType::FLOAT_LITERAL } else { TokenType::INTEGER_LITERAL },
-            value,
-            position: Position { line, column },
-        }
+fn to_json(token: &Token, source: &str) -> JsonToken {
+    let offsets = matches!(token.token_type, TokenType::StringLiteral(_)).then(|| {
+        string_offsets(&source[token.span.clone()], token.line, token.column)
+    });
+    let shifted = offsets.as_ref().is_some_and(|offsets| {
+        offsets.iter().enumerate().any(|(i, p)| p.line != token.line || p.column != token.column + 1 + i)
+    });
+    JsonToken {
+        token_type: type_name(token),
+        value: token.value.clone(),
+        position: Position { line: token.line, column: token.column },
+        offsets: offsets.filter(|_| shifted),
     }
+}
 
-    fn read_string(&mut self) -> Token {
-        let line = self.line;
-        let column = self.column;
-        self.advance(); // Skip opening "
-        let mut value = String::new();
-        while let Some(ch) = self.peek() {
-            if ch == '"' {
-                self.advance();
-                break;
-            }
-            if ch == '\\' {
-                self.advance();
-                if let Some(esc) = self.advance() {
-                    match esc {
-                        'n' => value.push('\n'),
-                        't' => value.push('\t'),
-                        'r' => value.push('\r'),
-                        '\\' => value.push('\\'),
-                        '\'' => value.push('\''),
-                        '0' => value.push('\0'),
-                        _ => value.push(esc),
-                    }
-                }
-            } else {
-                value.push(self.advance().unwrap());
-            }
-        }
-        Token { token_type: TokenType::STRING_LITERAL, value, position: Position { line, column } }
-    }
+fn report_error(error: &LexerError) -> ! {
+    let diag = Diagnostic {
+        code: "E000".to_string(),
+        message: error.message.clone(),
+        primary_span: Span {
+            line: error.line,
+            column: error.column,
+            length: 1,
+            label: "unexpected input here".to_string(),
+        },
+    };
+    eprintln!("{}", serde_json::to_string(&diag).unwrap());
+    std::process::exit(1);
+}
 
-    fn read_punctuation_or_operator(&mut self) -> Option<Token> {
-        let line = self.line;
-        let column = self.column;
-        let ch = self.advance().unwrap();
-        let mut value = ch.to_string();
-        
-        let token_type = match ch {
-            '(' => TokenType::LEFT_PAREN,
-            ')' => TokenType::RIGHT_PAREN,
-            '{' => TokenType::LEFT_BRACE,
-            '}' => TokenType::RIGHT_BRACE,
-            '[' => TokenType::LEFT_BRACKET,
-            ']' => TokenType::RIGHT_BRACKET,
-            ';' => TokenType::SEMICOLON,
-            ',' => TokenType::COMMA,
-            '.' => {
-                if self.peek() == Some('.') {
-                    value.push(self.advance().unwrap());
-                    TokenType::RANGE
-                } else { TokenType::DOT }
-            },
-            ':' => TokenType::COLON,
-            '=' => {
-                if self.peek() == Some('=') {
-                    value.push(self.advance().unwrap());
-                    TokenType::EQUAL
-                } else { TokenType::ASSIGN }
-            },
-            '+' => TokenType::PLUS,
-            '-' => {
-                if self.peek() == Some('>') {
-                    value.push(self.advance().unwrap());
-                    TokenType::ARROW
-                } else { TokenType::MINUS }
-            },
-            '*' => TokenType::MULTIPLY,
-            '/' => TokenType::DIVIDE,
-            '%' => TokenType::MODULO,
-            '<' => {
-                if self.peek() == Some('=') {
-                    value.push(self.advance().unwrap());
-                    TokenType::LESS_EQUAL
-                } else { TokenType::LESS_THAN }
-            },
-            '>' => {
-                if self.peek() == Some('=') {
-                    value.push(self.advance().unwrap());
-                    TokenType::GREATER_EQUAL
-                } else { TokenType::GREATER_THAN }
-            },
-            '!' => {
-                if self.peek() == Some('=') {
-                    value.push(self.advance().unwrap());
-                    TokenType::NOT_EQUAL
-                } else { TokenType::LOGICAL_NOT }
-            },
-            '&' => {
-                if self.peek() == Some('&') {
-                    value.push(self.advance().unwrap());
-                    TokenType::LOGICAL_AND
-                } else { TokenType::AMPERSAND }
-            },
-            '|' => {
-                if self.peek() == Some('|') {
-                    value.push(self.advance().unwrap());
-                    TokenType::LOGICAL_OR
-                } else { return None }
+/// Warns about every line whose indentation contains both tabs and spaces,
+/// whose columns then depend on the editor's tab width. Only the whitespace
+/// before the first token of a line counts, so the contents of multi-line
+/// strings and block comments are never mistaken for indentation.
+fn warn_mixed_indentation(source: &str, tokens: &[Token]) {
+    let mut previous_line = 0;
+    for token in tokens {
+        let first_on_line = token.line != previous_line;
+        previous_line = token.end_line;
+        if !first_on_line || token.token_type == TokenType::Eof { continue; }
+        let line_start = source[..token.span.start].rfind('\n').map_or(0, |i| i + 1);
+        let indent: Vec<char> = source[line_start..token.span.start].chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+        if !(indent.contains(&' ') && indent.contains(&'\t')) { continue; }
+        let diag = Diagnostic {
+            code: "W0034".to_string(),
+            message: "indentation mixes tabs and spaces".to_string(),
+            primary_span: Span {
+                line: token.line,
+                column: 1,
+                length: indent.len(),
+                label: "tabs and spaces in the same indentation".to_string(),
             },
-            _ => return None,
         };
+        eprintln!("{}", serde_json::to_string(&diag).unwrap());
+    }
+}
 
-        Some(Token { token_type, value, position: Position { line, column } })
+/// Renders tokens one per line as `LINE:COL  TOKEN_TYPE  "value"`, padded into columns.
+fn format_pretty(tokens: &[JsonToken]) -> String {
+    let positions: Vec<String> = tokens.iter().map(|t| format!("{}:{}", t.position.line, t.position.column)).collect();
+    let pos_width = positions.iter().map(String::len).max().unwrap_or(0);
+    let type_width = tokens.iter().map(|t| t.token_type.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for (pos, token) in positions.iter().zip(tokens) {
+        out.push_str(&format!("{:<pos_width$}  {:<type_width$}  {:?}\n", pos, token.token_type, token.value));
     }
+    out
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 { return; }
-    let input = fs::read_to_string(&args[1]).expect("Failed to read file");
-    let mut lexer = Lexer::new(input);
-    let tokens = lexer.tokenize();
-    println!("{}", serde_json::to_string(&tokens).unwrap());
+    let args: Vec<String> = env::args().skip(1).collect();
+    let pretty = args.iter().any(|a| a == "--pretty");
+    let check_indentation = args.iter().any(|a| a == "--check-indentation");
+    let Some(path) = args.iter().find(|a| !a.starts_with("--")) else { return };
+    let input = fs::read_to_string(path).expect("Failed to read file");
+    let tokens = match Lexer::new(&input).tokenize() {
+        Ok(tokens) => tokens,
+        Err(error) => report_error(&error),
+    };
+    if check_indentation {
+        warn_mixed_indentation(&input, &tokens);
+    }
+    let tokens: Vec<JsonToken> = tokens.iter().map(|t| to_json(t, &input)).collect();
+    if pretty {
+        print!("{}", format_pretty(&tokens));
+    } else {
+        println!("{}", serde_json::to_string(&tokens).unwrap());
+    }
 }
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_pretty_output_is_columnar() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "pretty.fax"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-lexer"))
+        .arg("--pretty")
+        .arg(path)
+        .output()
+        .expect("failed to run fax-lexer");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "1:1   LET              \"let\"");
+    assert_eq!(lines[3], "1:13  INTEGER_LITERAL  \"10\"");
+    assert_eq!(lines[7], "2:9   STRING_LITERAL   \"hi\"");
+    // Every value starts in the same column.
+    let value_columns: Vec<usize> = lines.iter().map(|l| l.find('"').unwrap()).collect();
+    assert!(value_columns.iter().all(|&c| c == value_columns[0]));
+}
+
+fn lex_checking_indentation(fixture: &str) -> std::process::Output {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture].iter().collect();
+    Command::new(env!("CARGO_BIN_EXE_fax-lexer"))
+        .arg("--check-indentation")
+        .arg(path)
+        .output()
+        .expect("failed to run fax-lexer")
+}
+
+#[test]
+fn test_tab_then_space_indentation_warns() {
+    let output = lex_checking_indentation("mixed_indent.fax");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let diags: Vec<serde_json::Value> = stderr.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "W0034");
+    assert_eq!(diags[0]["primary_span"]["line"], 2);
+    assert_eq!(diags[0]["primary_span"]["length"], 2);
+}
+
+#[test]
+fn test_consistent_indentation_does_not_warn() {
+    let output = lex_checking_indentation("tab_indent.fax");
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_tabs_and_spaces_inside_strings_and_comments_do_not_warn() {
+    let output = lex_checking_indentation("indent_in_string.fax");
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_escaped_string_keeps_source_offsets() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "string_offsets.fax"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-lexer")).arg(path).output().expect("failed to run fax-lexer");
+    assert!(output.status.success());
+    let tokens: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let strings: Vec<&serde_json::Value> = tokens.iter().filter(|t| t["type"] == "STRING_LITERAL").collect();
+    // `a\tb {}`: the decoded `{` is the fifth character, but `\t` takes two columns in the source.
+    assert_eq!(strings[0]["offsets"][4], serde_json::json!({ "line": 1, "column": 15 }));
+    // Without escapes every character sits at `column + 1 + index`, so no offsets are emitted.
+    assert!(strings[1].get("offsets").is_none());
+}
+
+#[test]
+fn test_hex_literals_around_range() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "hex_range.fax"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-lexer")).arg(path).output().expect("failed to run fax-lexer");
+    assert!(output.status.success());
+    let tokens: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let kinds: Vec<(&str, &str)> = tokens[3..6].iter().map(|t| (t["type"].as_str().unwrap(), t["value"].as_str().unwrap())).collect();
+    assert_eq!(kinds, [("INTEGER_LITERAL", "0x10"), ("RANGE", ".."), ("INTEGER_LITERAL", "0xff")]);
+}
+
+#[test]
+fn test_words_without_parser_grammar_stay_identifiers() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "reserved_words.fax"].iter().collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_fax-lexer")).arg(path).output().expect("failed to run fax-lexer");
+    assert!(output.status.success());
+    let tokens: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let kind = |value: &str| tokens.iter().find(|t| t["value"] == value).unwrap()["type"].clone();
+    assert_eq!(kind("char"), "CHAR");
+    assert_eq!(kind("static"), "IDENTIFIER");
+    assert_eq!(kind("loop"), "IDENTIFIER");
+}
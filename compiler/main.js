@@ -1,6 +1,6 @@
 #!/usr/bin/env node
 
-const { execSync } = require('child_process');
+const { execSync, spawnSync } = require('child_process');
 const fs = require('fs');
 const path = require('path');
 const os = require('os');
@@ -10,6 +10,7 @@ const { PluginManager } = require('../utils/plugin-system');
 const c = {
     red: (t) => `\x1b[38;5;196m${t}\x1b[0m`,
     boldRed: (t) => `\x1b[1;38;5;196m${t}\x1b[0m`,
+    boldYellow: (t) => `\x1b[1;38;5;214m${t}\x1b[0m`,
     info: (t) => `\x1b[38;5;33m${t}\x1b[0m`,
     success: (t) => `\x1b[1;32m${t}\x1b[0m`,
     bold: (t) => `\x1b[1m${t}\x1b[22m`,
@@ -17,6 +18,26 @@ const c = {
     gutter: (t) => `\x1b[38;5;33m${t}\x1b[0m`
 };
 
+const WRAP_WIDTH = 80;
+const GUTTER = '    | ';
+
+// Greedy word wrap to `width` columns (at least 20); a word longer than that gets a line of its own.
+function wrapText(text, width) {
+    const limit = Math.max(20, width);
+    const lines = [];
+    let current = '';
+    for (const word of text.split(/\s+/).filter(Boolean)) {
+        if (current && current.length + 1 + word.length > limit) {
+            lines.push(current);
+            current = word;
+        } else {
+            current = current ? `${current} ${word}` : word;
+        }
+    }
+    lines.push(current);
+    return lines;
+}
+
 class FaxCompiler {
     constructor(options = {}) {
         this.config = this.loadConfig();
@@ -47,58 +68,86 @@ class FaxCompiler {
         return defaultConfig;
     }
 
-    renderSpan(source, span, isPrimary) {
-        if (!span || !span.line || span.line === 0) return;
+    static renderSpan(source, span, isPrimary) {
+        if (!span || !span.line || span.line === 0) return [];
         const lineIdx = span.line - 1;
-        if (lineIdx < 0 || lineIdx >= source.length) return;
+        if (lineIdx < 0 || lineIdx >= source.length) return [];
         const lineContent = source[lineIdx] || "";
         const gutter = c.gutter(`${span.line.toString().padStart(3, ' ')} | `);
-        console.error(gutter + lineContent);
         const pointerChar = isPrimary ? '^' : '-';
         const pointerColor = isPrimary ? c.boldRed : c.info;
-        const pointer = ' '.repeat(Math.max(0, span.column - 1)) + pointerColor(pointerChar.repeat(Math.max(1, span.length || 1)));
-        const label = span.label ? ` ${pointerColor(span.label)}` : '';
-        console.error(c.gutter('    | ') + pointer + label);
+        const offset = Math.max(0, span.column - 1);
+        const width = Math.max(1, span.length || 1);
+        const pointer = ' '.repeat(offset) + pointerColor(pointerChar.repeat(width));
+        // Wrapped label lines continue under the first one, right of the carets.
+        const labelStart = offset + width + 1;
+        const [first, ...rest] = span.label ? wrapText(span.label, WRAP_WIDTH - GUTTER.length - labelStart) : [];
+        const lines = [gutter + lineContent, c.gutter(GUTTER) + pointer + (first ? ` ${pointerColor(first)}` : '')];
+        rest.forEach(line => lines.push(c.gutter(GUTTER) + ' '.repeat(labelStart) + pointerColor(line)));
+        return lines;
+    }
+
+    static renderNote(note) {
+        const prefix = '  = note: ';
+        return note.split('\n').flatMap(paragraph => {
+            const [first, ...rest] = wrapText(paragraph, WRAP_WIDTH - prefix.length);
+            return [`${c.info('  = note')}: ${first}`, ...rest.map(line => ' '.repeat(prefix.length) + line)];
+        });
+    }
+
+    static formatDiagnostic(diag, source, sourcePath) {
+        const lines = [];
+        const header = diag.code.startsWith('W') ? c.boldYellow('warning[' + diag.code + ']') : c.boldRed('error[' + diag.code + ']');
+        lines.push(`\n${header}: ${c.bold(diag.message)}`);
+        lines.push(`${c.info('  -->')} ${sourcePath}:${diag.primary_span.line}:${diag.primary_span.column}`);
+        lines.push(c.gutter('    |'));
+        if (diag.secondary_spans) diag.secondary_spans.forEach(s => lines.push(...FaxCompiler.renderSpan(source, s, false)));
+        lines.push(...FaxCompiler.renderSpan(source, diag.primary_span, true));
+        lines.push(c.gutter('    |'));
+        if (diag.suggestion) {
+            lines.push(`${c.info('help')}: ${diag.suggestion.message}`);
+            lines.push(c.gutter('    |'));
+            lines.push(`${c.info('  + ')}${c.success(diag.suggestion.replacement)}`);
+            lines.push(c.gutter('    |'));
+        }
+        if (diag.note) lines.push(...FaxCompiler.renderNote(diag.note));
+        return lines;
     }
 
     reportDiagnostic(diag, sourcePath) {
         try {
             const source = fs.readFileSync(sourcePath, 'utf-8').split('\n');
-            console.error(`\n${c.boldRed('error[' + diag.code + ']')}: ${c.bold(diag.message)}`);
-            console.error(`${c.info('  -->')} ${sourcePath}:${diag.primary_span.line}:${diag.primary_span.column}`);
-            console.error(c.gutter('    |'));
-            if (diag.secondary_spans) diag.secondary_spans.forEach(s => this.renderSpan(source, s, false));
-            this.renderSpan(source, diag.primary_span, true);
-            console.error(c.gutter('    |'));
-            if (diag.suggestion) {
-                console.error(`${c.info('help')}: ${diag.suggestion.message}`);
-                console.error(c.gutter('    |'));
-                console.error(`${c.info('  + ')}${c.success(diag.suggestion.replacement)}`);
-                console.error(c.gutter('    |'));
-            }
-            if (diag.note) console.error(`${c.info('  = note')}: ${diag.note}`);
+            FaxCompiler.formatDiagnostic(diag, source, sourcePath).forEach(line => console.error(line));
             const code = diag.code;
-            console.log(`\n${c.gray('For more information about this error, try `faxc --explain ' + code + '`')}\n`);
+            const kind = code.startsWith('W') ? 'warning' : 'error';
+            console.log(`\n${c.gray('For more information about this ' + kind + ', try `faxc --explain ' + code + '`')}\n`);
         } catch (e) {
             console.error(c.boldRed('\n[DIAGNOSTIC RENDER ERROR]: ') + JSON.stringify(diag));
         }
     }
 
+    // Diagnostics a phase wrote to stderr: a JSON array (checker) or one JSON object per line (analyzer).
+    static parseDiagnostics(stderr) {
+        return stderr.trim().split('\n').flatMap(line => {
+            line = line.trim();
+            if (line.startsWith('[') && line.endsWith(']')) return JSON.parse(line);
+            if (line.startsWith('{') && line.endsWith('}')) return [JSON.parse(line)];
+            return [];
+        });
+    }
+
     runPhase(name, command) {
-        try {
-            return execSync(command, { stdio: ['pipe', 'pipe', 'pipe'] }).toString();
-        } catch (err) {
-            const output = err.stderr.toString().trim().split('\n');
-            const lastLine = output[output.length - 1];
-            if (lastLine.startsWith('{') && lastLine.endsWith('}')) {
-                this.reportDiagnostic(JSON.parse(lastLine), this.options.inputFile);
-                process.exit(1);
-            } else {
-                console.error(`${c.boldRed('error')}: compilation phase "${name}" failed`);
-                console.error(c.gray(err.stderr.toString()));
-                process.exit(1);
-            }
+        const child = spawnSync(command, { shell: true, stdio: ['pipe', 'pipe', 'pipe'] });
+        const stderr = child.stderr.toString();
+        const diagnostics = FaxCompiler.parseDiagnostics(stderr);
+        // Warnings leave the exit code at 0 but are still shown.
+        diagnostics.forEach(diag => this.reportDiagnostic(diag, this.options.inputFile));
+        if (child.status === 0) return child.stdout.toString();
+        if (diagnostics.length === 0) {
+            console.error(`${c.boldRed('error')}: compilation phase "${name}" failed`);
+            console.error(c.gray(stderr));
         }
+        process.exit(1);
     }
 
     async compile() {
@@ -145,17 +194,16 @@ if (require.main === module) {
     const args = process.argv.slice(2);
     const compiler = new FaxCompiler();
     if (args.includes('--explain')) {
-        const ERROR_DB = require('./errors/database');
-        const code = args[args.indexOf('--explain') + 1];
-        const error = ERROR_DB[code];
-        if (error) {
-            console.log(`\n${c.info(c.bold(code))} - ${c.bold(error.title)}`);
-            console.log(`${c.gutter('  |')}`);
-            error.explanation.split('\n').forEach(line => console.log(`${c.gutter('  |')} ${line}`));
+        // The checker keeps the explanations for every stage's codes.
+        const code = args[args.indexOf('--explain') + 1] || '';
+        try {
+            const text = execSync(`cargo run --quiet --manifest-path compiler/checker/Cargo.toml -- --explain "${code}"`, { stdio: ['pipe', 'pipe', 'pipe'] }).toString();
+            const [title, , ...explanation] = text.trimEnd().slice(code.length + 2).split('\n');
+            console.log(`\n${c.info(c.bold(code.toUpperCase()))} - ${c.bold(title)}`);
             console.log(`${c.gutter('  |')}`);
-            console.log(`${c.info('  = ')}${c.bold('solution')}: ${error.solution}`);
+            explanation.forEach(line => console.log(`${c.gutter('  |')} ${line}`));
             console.log(`${c.gutter('  |')}\n`);
-        } else {
+        } catch (e) {
             console.error(`${c.red('error:')} code ${code} not found`);
         }
     } else if (args.length > 0) {
@@ -1,35 +1,46 @@
 import * as fs from 'fs';
 
 enum TokenType {
-    LET = "LET", VAR = "VAR", CONST = "CONST", FN = "FN", STRUCT = "STRUCT",
+    LET = "LET", VAR = "VAR", CONST = "CONST", FN = "FN", STRUCT = "STRUCT", ENUM = "ENUM", IMPL = "IMPL",
     IF = "IF", ELSE = "ELSE", WHILE = "WHILE", FOR = "FOR", RETURN = "RETURN",
-    BREAK = "BREAK", CONTINUE = "CONTINUE",
-    IMPORT = "IMPORT", PTR = "PTR", REF = "REF", SELF = "SELF",
-    INT = "INT", FLOAT = "FLOAT", BOOL = "BOOL", STRING = "STRING", VOID = "VOID",
-    TRUE = "TRUE", FALSE = "FALSE", IDENTIFIER = "IDENTIFIER",
+    BREAK = "BREAK", CONTINUE = "CONTINUE", MATCH = "MATCH",
+    IMPORT = "IMPORT", PTR = "PTR", REF = "REF", SELF = "SELF", MUT = "MUT",
+    INT = "INT", FLOAT = "FLOAT", BOOL = "BOOL", STRING = "STRING", CHAR = "CHAR", VOID = "VOID",
+    AS = "AS",
+    TRUE = "TRUE", FALSE = "FALSE", NULL = "NULL", IDENTIFIER = "IDENTIFIER",
     INTEGER_LITERAL = "INTEGER_LITERAL", FLOAT_LITERAL = "FLOAT_LITERAL",
     STRING_LITERAL = "STRING_LITERAL", PLUS = "PLUS", MINUS = "MINUS",
     MULTIPLY = "MULTIPLY", DIVIDE = "DIVIDE", MODULO = "MODULO",
+    PLUS_ASSIGN = "PLUS_ASSIGN", MINUS_ASSIGN = "MINUS_ASSIGN", MULTIPLY_ASSIGN = "MULTIPLY_ASSIGN", DIVIDE_ASSIGN = "DIVIDE_ASSIGN", MODULO_ASSIGN = "MODULO_ASSIGN",
     ASSIGN = "ASSIGN", EQUAL = "EQUAL", NOT_EQUAL = "NOT_EQUAL",
-    LESS_THAN = "LESS_THAN", GREATER_THAN = "GREATER_THAN",
+    LESS_THAN = "LESS_THAN", GREATER_THAN = "GREATER_THAN", RIGHT_SHIFT = "RIGHT_SHIFT", POWER = "POWER",
     LESS_EQUAL = "LESS_EQUAL", GREATER_EQUAL = "GREATER_EQUAL",
-    LOGICAL_NOT = "LOGICAL_NOT", LOGICAL_AND = "LOGICAL_AND", LOGICAL_OR = "LOGICAL_OR", AMPERSAND = "AMPERSAND", RANGE = "RANGE",
+    LOGICAL_NOT = "LOGICAL_NOT", LOGICAL_AND = "LOGICAL_AND", LOGICAL_OR = "LOGICAL_OR", AMPERSAND = "AMPERSAND", PIPE = "PIPE", CARET = "CARET", RANGE = "RANGE",
     LEFT_PAREN = "LEFT_PAREN", RIGHT_PAREN = "RIGHT_PAREN",
     LEFT_BRACE = "LEFT_BRACE", RIGHT_BRACE = "RIGHT_BRACE",
     LEFT_BRACKET = "LEFT_BRACKET", RIGHT_BRACKET = "RIGHT_BRACKET",
     SEMICOLON = "SEMICOLON", COMMA = "COMMA", DOT = "DOT",
-    COLON = "COLON", ARROW = "ARROW", EOF = "EOF"
+    COLON = "COLON", QUESTION = "QUESTION", NULL_COALESCE = "NULL_COALESCE", ARROW = "ARROW", FAT_ARROW = "FAT_ARROW", LABEL = "LABEL", AT = "AT", EOF = "EOF"
 }
 
+const COMPOUND_OPERATORS: Partial<Record<TokenType, string>> = {
+    [TokenType.PLUS_ASSIGN]: "+", [TokenType.MINUS_ASSIGN]: "-", [TokenType.MULTIPLY_ASSIGN]: "*",
+    [TokenType.DIVIDE_ASSIGN]: "/", [TokenType.MODULO_ASSIGN]: "%",
+};
+
 interface Token {
     type: TokenType;
     value: string;
     position: { line: number, column: number };
+    // Source position of each character of a string literal, when escapes shift them.
+    offsets?: { line: number, column: number }[];
 }
 
 class Parser {
     private tokens: Token[];
     private pos: number = 0;
+    // Off while parsing the producer of `if let` / `while let`, where `{` opens the body.
+    private allowStructLiteral: boolean = true;
 
     constructor(tokens: Token[]) {
         this.tokens = tokens;
@@ -69,6 +80,25 @@ ${this.peek().value}
         return false;
     }
 
+    // Parses `item (, item)* ,?` up to and including `close`. A single trailing
+    // comma is allowed; an empty slot such as `f(a,,b)` is rejected.
+    private parseCommaList<T>(close: TokenType, parseItem: () => T): T[] {
+        const items: T[] = [];
+        while (this.peek().type !== close) {
+            this.rejectStrayComma();
+            items.push(parseItem());
+            if (!this.match(TokenType.COMMA)) break;
+        }
+        this.expect(close);
+        return items;
+    }
+
+    private rejectStrayComma() {
+        if (this.peek().type === TokenType.COMMA) {
+            this.reportError("expected an item, found `,`", "remove this extra comma");
+        }
+    }
+
     parse() {
         const body = [];
         while (this.peek().type !== TokenType.EOF) {
@@ -81,17 +111,23 @@ ${this.peek().value}
         const token = this.peek();
         switch (token.type) {
             case TokenType.LET:
+            case TokenType.VAR:
             case TokenType.CONST: return this.parseVariableDeclaration();
             case TokenType.FN: return this.parseFunctionDeclaration();
             case TokenType.STRUCT: return this.parseStructDeclaration();
+            case TokenType.ENUM: return this.parseEnumDeclaration();
+            case TokenType.AT: return this.parseAttributed();
+            case TokenType.IMPL: return this.parseImplBlock();
             case TokenType.RETURN: return this.parseReturnStatement();
             case TokenType.IMPORT: return this.parseImportStatement();
             case TokenType.IF: return this.parseIfStatement();
             case TokenType.WHILE: return this.parseWhileStatement();
             case TokenType.FOR: return this.parseForStatement();
+            case TokenType.MATCH: return this.parseMatchStatement();
             case TokenType.BREAK: return this.parseBreakStatement();
             case TokenType.CONTINUE: return this.parseContinueStatement();
             case TokenType.LEFT_BRACE: return this.parseBlock();
+            case TokenType.LABEL: return this.parseLabeledBlock();
             default:
                 const expr = this.parseExpression();
                 this.expect(TokenType.SEMICOLON, "expected semicolon at the end of this expression");
@@ -102,7 +138,9 @@ ${this.peek().value}
     private parseVariableDeclaration() {
         const token = this.peek();
         const isConstant = token.type === TokenType.CONST;
-        this.advance(); // let or const
+        const mutable = token.type === TokenType.VAR;
+        this.advance(); // let, var or const
+        if (this.peek().type === TokenType.LEFT_PAREN) return this.parseTupleDeclaration(token, isConstant);
         const id = this.expect(TokenType.IDENTIFIER, "expected a variable name after keyword").value;
         let dataType = "auto";
         if (this.match(TokenType.COLON)) {
@@ -118,20 +156,43 @@ ${this.peek().value}
             identifier: id, 
             dataType,
             isConstant,
+            mutable,
             initializer,
             position: { line: token.position.line, column: token.position.column }
         };
     }
 
+    // `let (a, b) = value;` binds the elements of a tuple to new variables.
+    private parseTupleDeclaration(token: Token, isConstant: boolean) {
+        this.advance(); // (
+        const names = this.parseCommaList(TokenType.RIGHT_PAREN, () => this.expect(TokenType.IDENTIFIER, "expected a variable name").value);
+        this.expect(TokenType.ASSIGN, "a tuple pattern must be initialized");
+        const initializer = this.parseExpression();
+        this.expect(TokenType.SEMICOLON, "variable declarations must end with a semicolon");
+        return { type: "TupleDeclaration", names, isConstant, initializer, position: token.position };
+    }
+
     private parseType(): string {
         const token = this.peek();
-        if ([TokenType.IDENTIFIER, TokenType.INT, TokenType.FLOAT, TokenType.STRING, TokenType.BOOL, TokenType.VOID, TokenType.PTR, TokenType.REF].includes(token.type)) {
+        // `(T, U)` is the tuple type `tuple<T, U>`.
+        if (this.match(TokenType.LEFT_PAREN)) {
+            const elements = this.parseCommaList(TokenType.RIGHT_PAREN, () => this.parseType());
+            return `tuple<${elements.join(", ")}>`;
+        }
+        if ([TokenType.IDENTIFIER, TokenType.INT, TokenType.FLOAT, TokenType.STRING, TokenType.BOOL, TokenType.CHAR, TokenType.VOID, TokenType.PTR, TokenType.REF].includes(token.type)) {
             let type = this.advance().value;
             if (this.match(TokenType.LESS_THAN)) {
-                const inner = this.parseType();
-                this.expect(TokenType.GREATER_THAN);
+                let inner = this.parseType();
+                if (this.match(TokenType.COMMA)) {
+                    // Fixed-size arrays: `array<T, N>` where N is a literal or a const name.
+                    const size = this.peek().type === TokenType.INTEGER_LITERAL ? this.advance().value : this.expect(TokenType.IDENTIFIER).value;
+                    inner = `${inner}, ${size}`;
+                }
+                this.expectTypeClose();
                 type = `${type}<${inner}>`;
             }
+            // `T?` is shorthand for `optional<T>`.
+            if (this.match(TokenType.QUESTION)) type = `optional<${type}>`;
             while (this.match(TokenType.LEFT_BRACKET)) {
                 this.expect(TokenType.RIGHT_BRACKET);
                 type += "[]";
@@ -141,39 +202,59 @@ ${this.peek().value}
         this.reportError("invalid type name", "expected a type here");
     }
 
+    // Closes a type argument list. `>>` is lexed as one shift token, so in
+    // `ptr<ptr<int>>` the inner list takes its first half and leaves the second.
+    private expectTypeClose() {
+        const token = this.peek();
+        if (token.type !== TokenType.RIGHT_SHIFT) {
+            this.expect(TokenType.GREATER_THAN);
+            return;
+        }
+        this.tokens[this.pos] = { ...token, type: TokenType.GREATER_THAN, value: ">", position: { ...token.position, column: token.position.column + 1 } };
+    }
+
     private parseFunctionDeclaration() {
         const token = this.peek();
         this.advance(); // fn
         const name = this.expect(TokenType.IDENTIFIER).value;
         this.expect(TokenType.LEFT_PAREN);
-        const params: any[] = [];
-        if (this.peek().type !== TokenType.RIGHT_PAREN) {
-            do {
-                if (this.match(TokenType.SELF)) {
-                    params.push({ name: "self", type: "self" });
-                } else {
-                    const pName = this.expect(TokenType.IDENTIFIER).value;
-                    this.expect(TokenType.COLON);
-                    const pType = this.parseType();
-                    params.push({ name: pName, type: pType });
-                }
-            } while (this.match(TokenType.COMMA));
-        }
-        this.expect(TokenType.RIGHT_PAREN);
+        const params: any[] = this.parseCommaList(TokenType.RIGHT_PAREN, () => {
+            if (this.match(TokenType.SELF)) return { name: "self", type: "self" };
+            const pName = this.expect(TokenType.IDENTIFIER).value;
+            this.expect(TokenType.COLON);
+            const { type: pType, lifetime } = this.parseSignatureType();
+            return lifetime ? { name: pName, type: pType, lifetime } : { name: pName, type: pType };
+        });
         let returnType = "void";
-        if (this.match(TokenType.ARROW)) returnType = this.parseType();
+        let returnLifetime: string | undefined;
+        if (this.match(TokenType.ARROW)) ({ type: returnType, lifetime: returnLifetime } = this.parseSignatureType());
         const body = this.parseBlock();
-        return { type: "FunctionDeclaration", name, params, returnType, body, position: token.position };
+        const fn: any = { type: "FunctionDeclaration", name, params, returnType, body, position: token.position };
+        if (returnLifetime) fn.returnLifetime = returnLifetime;
+        return fn;
+    }
+
+    // A parameter or return type; `&'a T` is the reference `ref<T>` with lifetime `a`.
+    private parseSignatureType(): { type: string, lifetime?: string } {
+        if (!this.match(TokenType.AMPERSAND)) return { type: this.parseType() };
+        const lifetime = this.peek().type === TokenType.LABEL ? this.advance().value : undefined;
+        return { type: `ref<${this.parseType()}>`, lifetime };
     }
 
     private parseStructDeclaration() {
         const token = this.peek();
         this.advance(); // struct
         const name = this.expect(TokenType.IDENTIFIER).value;
+        // `struct Box<T> { ... }` declares type parameters, substituted wherever `Box<int>` is used.
+        let typeParams: string[] = [];
+        if (this.match(TokenType.LESS_THAN)) {
+            typeParams = this.parseCommaList(TokenType.GREATER_THAN, () => this.expect(TokenType.IDENTIFIER, "expected a type parameter name").value);
+        }
         this.expect(TokenType.LEFT_BRACE);
         const fields: any[] = [];
         const methods: any[] = [];
         while (this.peek().type !== TokenType.RIGHT_BRACE && this.peek().type !== TokenType.EOF) {
+            this.rejectStrayComma();
             if (this.peek().type === TokenType.FN) {
                 methods.push(this.parseFunctionDeclaration());
             } else {
@@ -185,7 +266,55 @@ ${this.peek().value}
             }
         }
         this.expect(TokenType.RIGHT_BRACE);
-        return { type: "StructDeclaration", name, fields, methods, position: token.position };
+        return { type: "StructDeclaration", name, typeParams, fields, methods, position: token.position };
+    }
+
+    private parseEnumDeclaration() {
+        const token = this.peek();
+        this.advance(); // enum
+        const name = this.expect(TokenType.IDENTIFIER).value;
+        this.expect(TokenType.LEFT_BRACE);
+        const variants = this.parseCommaList(TokenType.RIGHT_BRACE, () => {
+            const nameToken = this.expect(TokenType.IDENTIFIER);
+            const variant: any = { name: nameToken.value, position: nameToken.position };
+            if (this.match(TokenType.LEFT_PAREN)) {
+                variant.payload = this.parseType();
+                this.expect(TokenType.RIGHT_PAREN, "expected `)` after the payload type");
+            }
+            if (this.match(TokenType.ASSIGN)) variant.value = this.parseExpression();
+            return variant;
+        });
+        return { type: "EnumDeclaration", name, variants, position: token.position };
+    }
+
+    // `@name` attributes; only enums take them, and `@bitflags` is the only one so far.
+    private parseAttributed() {
+        const attributes: string[] = [];
+        while (this.match(TokenType.AT)) {
+            const name = this.peek();
+            this.expect(TokenType.IDENTIFIER, "expected an attribute name");
+            if (name.value !== "bitflags") {
+                this.pos--;
+                this.reportError(`unknown attribute \`@${name.value}\``, "not a known attribute");
+            }
+            attributes.push(name.value);
+        }
+        if (this.peek().type !== TokenType.ENUM) this.reportError("attributes can only be applied to an enum", "expected `enum` here");
+        return { ...this.parseEnumDeclaration(), attributes };
+    }
+
+    private parseImplBlock() {
+        const token = this.peek();
+        this.advance(); // impl
+        const typeName = this.expect(TokenType.IDENTIFIER).value;
+        this.expect(TokenType.LEFT_BRACE);
+        const methods: any[] = [];
+        while (this.peek().type !== TokenType.RIGHT_BRACE && this.peek().type !== TokenType.EOF) {
+            if (this.peek().type !== TokenType.FN) this.reportError("expected `fn` in impl block", "only methods can appear here");
+            methods.push(this.parseFunctionDeclaration());
+        }
+        this.expect(TokenType.RIGHT_BRACE);
+        return { type: "ImplBlock", typeName, methods, position: token.position };
     }
 
     private parseImportStatement() {
@@ -195,9 +324,29 @@ ${this.peek().value}
         return { type: "ImportStatement", path: pathToken };
     }
 
+    // `let name[: type] = producer` in `if let` / `while let`; the body runs when the producer yields a value.
+    private parseLetBinding() {
+        this.advance(); // let
+        const name = this.expect(TokenType.IDENTIFIER, "expected a binding name after `let`").value;
+        let dataType = "auto";
+        if (this.match(TokenType.COLON)) dataType = this.parseType();
+        this.expect(TokenType.ASSIGN, "expected `=` after the binding");
+        this.allowStructLiteral = false;
+        const test = this.parseExpression();
+        this.allowStructLiteral = true;
+        return { binding: { name, type: dataType }, test };
+    }
+
     private parseIfStatement() {
         const token = this.peek();
         this.advance(); // if
+        if (this.peek().type === TokenType.LET) {
+            const { binding, test } = this.parseLetBinding();
+            const consequent = this.parseBlock();
+            let alternate = null;
+            if (this.match(TokenType.ELSE)) alternate = this.parseStatement();
+            return { type: "IfStatement", test, consequent, alternate, binding, position: token.position };
+        }
         this.expect(TokenType.LEFT_PAREN);
         const test = this.parseExpression();
         this.expect(TokenType.RIGHT_PAREN);
@@ -210,6 +359,10 @@ ${this.peek().value}
     private parseWhileStatement() {
         const token = this.peek();
         this.advance();
+        if (this.peek().type === TokenType.LET) {
+            const { binding, test } = this.parseLetBinding();
+            return { type: "WhileStatement", test, body: this.parseBlock(), binding, position: token.position };
+        }
         this.expect(TokenType.LEFT_PAREN);
         const test = this.parseExpression();
         this.expect(TokenType.RIGHT_PAREN);
@@ -217,6 +370,46 @@ ${this.peek().value}
         return { type: "WhileStatement", test, body, position: token.position };
     }
 
+    // `match value { pattern => body, ... }`; a body is a block or a single expression.
+    private parseMatchStatement() {
+        const token = this.peek();
+        this.advance(); // match
+        this.allowStructLiteral = false;
+        const discriminant = this.parseExpression();
+        this.allowStructLiteral = true;
+        this.expect(TokenType.LEFT_BRACE);
+        const arms: any[] = [];
+        while (this.peek().type !== TokenType.RIGHT_BRACE && this.peek().type !== TokenType.EOF) {
+            const armToken = this.peek();
+            const pattern = this.parsePattern();
+            this.expect(TokenType.FAT_ARROW, "expected `=>` after the pattern");
+            const body = this.peek().type === TokenType.LEFT_BRACE
+                ? this.parseBlock()
+                : { type: "ExpressionStatement", expression: this.parseExpression() };
+            arms.push({ pattern, body, position: armToken.position });
+            if (!this.match(TokenType.COMMA)) break;
+        }
+        this.expect(TokenType.RIGHT_BRACE);
+        return { type: "MatchStatement", discriminant, arms, position: token.position };
+    }
+
+    // `Enum.Variant(name)` or `Enum.Variant(name: type)` binds a variant's payload; anything else is a value.
+    private parsePattern() {
+        const isVariant = this.peek().type === TokenType.IDENTIFIER
+            && this.tokens[this.pos + 1]?.type === TokenType.DOT
+            && this.tokens[this.pos + 2]?.type === TokenType.IDENTIFIER
+            && this.tokens[this.pos + 3]?.type === TokenType.LEFT_PAREN;
+        if (!isVariant) return this.parseExpression();
+        const token = this.advance();
+        this.advance(); // .
+        const variant = this.advance().value;
+        this.advance(); // (
+        const name = this.expect(TokenType.IDENTIFIER, "expected a name for the variant's payload").value;
+        const type = this.match(TokenType.COLON) ? this.parseType() : "auto";
+        this.expect(TokenType.RIGHT_PAREN);
+        return { type: "VariantPattern", enumName: token.value, variant, binding: { name, type }, position: token.position };
+    }
+
     private parseForStatement() {
         const token = this.peek();
         this.advance(); // for
@@ -252,8 +445,11 @@ ${this.peek().value}
     private parseBreakStatement() {
         const token = this.peek();
         this.advance(); // break
+        const label = this.match(TokenType.LABEL) ? this.tokens[this.pos - 1].value : null;
+        let argument = null;
+        if (this.peek().type !== TokenType.SEMICOLON) argument = this.parseExpression();
         this.expect(TokenType.SEMICOLON);
-        return { type: "BreakStatement", position: token.position };
+        return { type: "BreakStatement", label, argument, position: token.position };
     }
 
     private parseContinueStatement() {
@@ -274,15 +470,65 @@ ${this.peek().value}
         return { type: "BlockStatement", body, position: token.position };
     }
 
+    // `'name: { ... }` - a block that `break 'name value;` can leave early with a value.
+    private parseLabeledBlock() {
+        const label = this.advance().value;
+        this.expect(TokenType.COLON, "expected `:` after the label");
+        return { ...this.parseBlock(), label };
+    }
+
+    // `Name {` only starts a struct literal when followed by `}` or `field:`,
+    // so a block after a bare identifier is never mistaken for one.
+    private atStructLiteralBody(): boolean {
+        if (!this.allowStructLiteral || this.peek().type !== TokenType.LEFT_BRACE) return false;
+        const next = this.tokens[this.pos + 1];
+        const afterNext = this.tokens[this.pos + 2];
+        const literals = [TokenType.INTEGER_LITERAL, TokenType.FLOAT_LITERAL, TokenType.STRING_LITERAL, TokenType.TRUE, TokenType.FALSE, TokenType.NULL];
+        return next?.type === TokenType.RIGHT_BRACE
+            || (next?.type === TokenType.IDENTIFIER && [TokenType.COLON, TokenType.COMMA].includes(afterNext?.type))
+            // Positional form: `Point { 1, 2 }`.
+            || literals.includes(next?.type);
+    }
+
+    private parseStructLiteral(nameToken: Token) {
+        this.expect(TokenType.LEFT_BRACE);
+        // Each field is `name: value`, or just a value for the positional form; the checker rejects a mix.
+        const fields = this.parseCommaList(TokenType.RIGHT_BRACE, () => {
+            if (this.peek().type === TokenType.IDENTIFIER && this.tokens[this.pos + 1]?.type === TokenType.COLON) {
+                const name = this.advance().value;
+                this.advance(); // :
+                return { name, value: this.parseExpression() };
+            }
+            return { name: null, value: this.parseExpression() };
+        });
+        return { type: "StructLiteral", name: nameToken.value, fields, position: nameToken.position };
+    }
+
     private parseExpression(): any {
         return this.parseAssignment();
     }
 
     private parseAssignment(): any {
-        const left = this.parseLogicalOr();
+        const left = this.parseNullCoalesce();
         if (this.match(TokenType.ASSIGN)) {
             return { type: "AssignmentExpression", left, right: this.parseExpression(), position: left.position };
         }
+        // `a += b` is parsed as `a = a + b`, so it is checked like any other assignment.
+        const compound = COMPOUND_OPERATORS[this.peek().type];
+        if (compound) {
+            this.advance();
+            const right = { type: "BinaryExpression", operator: compound, left, right: this.parseExpression(), position: left.position };
+            return { type: "AssignmentExpression", left, right, position: left.position };
+        }
+        return left;
+    }
+
+    // `a ?? b ?? c` groups as `a ?? (b ?? c)`.
+    private parseNullCoalesce(): any {
+        const left = this.parseLogicalOr();
+        if (this.match(TokenType.NULL_COALESCE)) {
+            return { type: "BinaryExpression", operator: "??", left, right: this.parseNullCoalesce(), position: left.position };
+        }
         return left;
     }
 
@@ -296,10 +542,35 @@ ${this.peek().value}
     }
 
     private parseLogicalAnd(): any {
-        let left = this.parseComparison();
+        let left = this.parseBitwiseOr();
         while (this.match(TokenType.LOGICAL_AND)) {
             const operator = "&&";
-            left = { type: "BinaryExpression", operator, left, right: this.parseComparison(), position: left.position };
+            left = { type: "BinaryExpression", operator, left, right: this.parseBitwiseOr(), position: left.position };
+        }
+        return left;
+    }
+
+    // Bitwise operators bind looser than comparisons, as in C: `a & b == c` is `a & (b == c)`.
+    private parseBitwiseOr(): any {
+        let left = this.parseBitwiseXor();
+        while (this.match(TokenType.PIPE)) {
+            left = { type: "BinaryExpression", operator: "|", left, right: this.parseBitwiseXor(), position: left.position };
+        }
+        return left;
+    }
+
+    private parseBitwiseXor(): any {
+        let left = this.parseBitwiseAnd();
+        while (this.match(TokenType.CARET)) {
+            left = { type: "BinaryExpression", operator: "^", left, right: this.parseBitwiseAnd(), position: left.position };
+        }
+        return left;
+    }
+
+    private parseBitwiseAnd(): any {
+        let left = this.parseComparison();
+        while (this.match(TokenType.AMPERSAND)) {
+            left = { type: "BinaryExpression", operator: "&", left, right: this.parseComparison(), position: left.position };
         }
         return left;
     }
@@ -323,24 +594,28 @@ ${this.peek().value}
     }
 
     private parseMultiplicative(): any {
-        let left = this.parseCall();
+        let left = this.parseCast();
         while ([TokenType.MULTIPLY, TokenType.DIVIDE, TokenType.MODULO].includes(this.peek().type)) {
             const operator = this.advance().value;
-            left = { type: "BinaryExpression", operator, left, right: this.parseCall(), position: left.position };
+            left = { type: "BinaryExpression", operator, left, right: this.parseCast(), position: left.position };
         }
         return left;
     }
 
+    private parseCast(): any {
+        let expr = this.parseCall();
+        while (this.match(TokenType.AS)) {
+            expr = { type: "CastExpression", expression: expr, targetType: this.parseType(), position: expr.position };
+        }
+        return expr;
+    }
+
     private parseCall(): any {
         let expr = this.parseMember();
         while (true) {
             const pos = expr.position;
             if (this.match(TokenType.LEFT_PAREN)) {
-                const args = [];
-                if (this.peek().type !== TokenType.RIGHT_PAREN) {
-                    do { args.push(this.parseExpression()); } while (this.match(TokenType.COMMA));
-                }
-                this.expect(TokenType.RIGHT_PAREN);
+                const args = this.parseCommaList(TokenType.RIGHT_PAREN, () => this.parseExpression());
                 expr = { type: "CallExpression", callee: expr, arguments: args, position: pos };
             } else if (this.match(TokenType.LEFT_BRACKET)) {
                 const start = this.parseExpression();
@@ -362,8 +637,15 @@ ${this.peek().value}
 
     private parseMember(): any {
         const token = this.peek();
+        // The lexer reads `**p` as one POWER token; in prefix position it is two derefs.
+        if (token.type === TokenType.POWER) {
+            this.tokens[this.pos] = { ...token, type: TokenType.MULTIPLY, value: "*", position: { ...token.position, column: token.position.column + 1 } };
+            return { type: "UnaryExpression", operator: "*", argument: this.parseMember() };
+        }
         if (this.match(TokenType.MINUS) || this.match(TokenType.LOGICAL_NOT) || this.match(TokenType.AMPERSAND) || this.match(TokenType.MULTIPLY)) {
-          return { type: "UnaryExpression", operator: token.value, argument: this.parseMember() };
+          // `&mut x` borrows `x` mutably; the analyzer keeps it from overlapping other borrows.
+          const operator = token.type === TokenType.AMPERSAND && this.match(TokenType.MUT) ? "&mut" : token.value;
+          return { type: "UnaryExpression", operator, argument: this.parseMember() };
         }
         return this.parsePrimary();
     }
@@ -373,24 +655,35 @@ ${this.peek().value}
         if (token.type === TokenType.EOF) {
             this.reportError("expected expression, found end of file", "incomplete expression here");
         }
-        if (this.match(TokenType.INTEGER_LITERAL)) return { type: "Literal", value: parseInt(token.value), position: token.position };
+        if (this.match(TokenType.INTEGER_LITERAL)) return { type: "Literal", value: Number(token.value), position: token.position };
         if (this.match(TokenType.FLOAT_LITERAL)) return { type: "Literal", value: parseFloat(token.value), position: token.position };
-        if (this.match(TokenType.STRING_LITERAL)) return { type: "Literal", value: token.value, position: token.position };
+        if (this.match(TokenType.STRING_LITERAL)) {
+            const literal: any = { type: "Literal", value: token.value, position: token.position };
+            if (token.offsets) literal.offsets = token.offsets;
+            return literal;
+        }
         if (this.match(TokenType.TRUE)) return { type: "Literal", value: true, position: token.position };
         if (this.match(TokenType.FALSE)) return { type: "Literal", value: false, position: token.position };
+        if (this.match(TokenType.NULL)) return { type: "Literal", value: null, position: token.position };
         if (this.match(TokenType.SELF)) return { type: "Identifier", name: "self", position: token.position };
-        if (this.match(TokenType.IDENTIFIER)) return { type: "Identifier", name: token.value, position: token.position };
+        if (this.match(TokenType.IDENTIFIER)) {
+            if (this.atStructLiteralBody()) return this.parseStructLiteral(token);
+            return { type: "Identifier", name: token.value, position: token.position };
+        }
+        if (token.type === TokenType.LABEL) return this.parseLabeledBlock();
         if (this.match(TokenType.LEFT_PAREN)) {
             const expr = this.parseExpression();
+            if (this.match(TokenType.COMMA)) {
+                const elements = [expr, ...this.parseCommaList(TokenType.RIGHT_PAREN, () => this.parseExpression())];
+                return { type: "TupleLiteral", elements, position: token.position };
+            }
             this.expect(TokenType.RIGHT_PAREN);
+            // Lints about operator precedence only apply to ungrouped operands.
+            if (expr.type === "BinaryExpression") expr.parenthesized = true;
             return expr;
         }
         if (this.match(TokenType.LEFT_BRACKET)) {
-            const elements = [];
-            if (this.peek().type !== TokenType.RIGHT_BRACKET) {
-                do { elements.push(this.parseExpression()); } while (this.match(TokenType.COMMA));
-            }
-            this.expect(TokenType.RIGHT_BRACKET);
+            const elements = this.parseCommaList(TokenType.RIGHT_BRACKET, () => this.parseExpression());
             return { type: "ArrayLiteral", elements, position: token.position };
         }
         this.reportError("expected expression", "found this instead");
@@ -52,6 +52,7 @@ namespace fax_std {
         }
         T& operator*() { return *data; }
         T* operator->() { return data; }
+        T& operator[](size_t index) { return data[index]; }
         T* get() { return data; }
         bool is_null() const { return data == nullptr; }
     };
@@ -25,11 +25,20 @@ class Transpiler:
             "while", "xor", "xor_eq"
         }
         self.user_symbols = set()
+        # Methods from `impl` blocks are emitted inside their struct's definition.
+        self.impl_methods = {}
+        # Field names of each struct in declaration order, which C++ designated initializers must follow.
+        self.struct_fields = {}
+        for stmt in ast["body"]:
+            if stmt["type"] == "ImplBlock": self.impl_methods.setdefault(stmt["typeName"], []).extend(stmt["methods"])
+            if stmt["type"] == "StructDeclaration": self.struct_fields[stmt["name"]] = [f["name"] for f in stmt["fields"]]
         for stmt in ast["body"]:
             if stmt["type"] == "FunctionDeclaration": self.user_symbols.add(self.mangle(stmt["name"]))
             if stmt["type"] == "VariableDeclaration": self.user_symbols.add(self.mangle(stmt["identifier"]))
-            if stmt["type"] == "StructDeclaration": self.user_symbols.add(self.mangle(stmt["name"]))
+            if stmt["type"] in ["StructDeclaration", "EnumDeclaration"]: self.user_symbols.add(self.mangle(stmt["name"]))
         self.local_scopes = []
+        # Optionals proven non-null by an enclosing `if (x != null)`; reads of them are unwrapped.
+        self.narrowed = []
 
     def enter_scope(self): self.local_scopes.append(set())
     def exit_scope(self): self.local_scopes.pop()
@@ -51,11 +60,17 @@ class Transpiler:
         if t == "Program": return self.gen_program(node)
         if t == "FunctionDeclaration": return self.gen_function(node)
         if t == "VariableDeclaration": return self.gen_variable(node)
+        if t == "TupleDeclaration":
+            for name in node["names"]: self.add_local(name)
+            names = ", ".join(self.mangle(n) for n in node["names"])
+            prefix = "const " if node.get("isConstant") else ""
+            return f"{self.get_indent()}{prefix}auto [{names}] = {self.generate(node['initializer'], no_paren=True)};"
         if t == "BlockStatement": return self.gen_block(node)
         if t == "ExpressionStatement": return self.get_indent() + self.generate(node['expression'], no_paren=True) + ";"
         if t == "IfStatement": return self.gen_if(node)
         if t == "WhileStatement": return self.gen_while(node)
         if t == "ForStatement": return self.gen_for(node)
+        if t == "MatchStatement": return self.gen_match(node)
         if t == "BreakStatement": return self.get_indent() + "break;"
         if t == "ContinueStatement": return self.get_indent() + "continue;"
         if t == "ReturnStatement":
@@ -66,11 +81,15 @@ class Transpiler:
         if t == "BinaryExpression": 
             l, r, op = self.generate(node['left']), self.generate(node['right']), node['operator']
             if op == "%=": return f"std::fmod({l}, {r})"
+            if op == "??":
+                # A variable narrowed by `if (x != null)` is already unwrapped.
+                if node['left'].get("type") == "Identifier" and node['left']['name'] in self.narrowed: return l
+                return f"{l}.value_or({r})"
             res = f"{l} {op} {r}"
             return f"({res})" if not no_paren else res
         if t == "UnaryExpression":
             op, arg = node['operator'], self.generate(node['argument'], no_paren=True)
-            if op == "&": return f"&({arg})"
+            if op in ("&", "&mut"): return f"&({arg})"
             return f"{op}{arg}"
         if t == "CallExpression": return self.gen_call(node)
         if t == "MemberExpression": 
@@ -88,17 +107,39 @@ class Transpiler:
             start = self.generate(node['start'], no_paren=True)
             end = self.generate(node['end'], no_paren=True)
             return f"fax_std::Array<decltype({obj})::value_type>({obj}.begin() + {start}, {obj}.begin() + {end})"
+        if t == "StructLiteral":
+            # Positional fields (no name) become plain aggregate initializers in declaration order;
+            # named ones may be written in any order, so they are sorted into it.
+            fields = node["fields"]
+            order = self.struct_fields.get(node["name"], [])
+            if all(f.get("name") in order for f in fields):
+                fields = sorted(fields, key=lambda f: order.index(f["name"]))
+            inits = ", ".join([(f".{self.mangle(f['name'])} = " if f.get("name") else "") + self.generate(f['value'], no_paren=True) for f in fields])
+            return f"{self.map_type(node['name'])}{{{inits}}}"
+        if t == "CastExpression":
+            return f"static_cast<{self.map_type(node['targetType'])}>({self.generate(node['expression'], no_paren=True)})"
         if t == "Literal": return self.gen_literal(node)
         if t == "Identifier":
             if node["name"] == "self": return "(*this)"
             m = self.mangle(node["name"])
+            if node["name"] in self.narrowed: return f"(*{m})"
             if self.is_local(node["name"]): return m
             if m in self.user_symbols and self.current_indent >= 2: return f"fax_app::{m}"
             return m
         if t == "ArrayLiteral": 
             elems = ", ".join([self.generate(e, no_paren=True) for e in node["elements"]])
             return "{" + elems + "}"
+        if t == "TupleLiteral":
+            return "std::make_tuple(" + ", ".join([self.generate(e, no_paren=True) for e in node["elements"]]) + ")"
         if t == "StructDeclaration": return self.gen_struct(node)
+        if t == "EnumDeclaration":
+            name = self.mangle(node['name'])
+            variants = ", ".join([self.mangle(v["name"]) + (f" = {self.generate(v['value'])}" if "value" in v else "") for v in node["variants"]])
+            code = f"{self.get_indent()}enum class {name} {{ {variants} }};"
+            if "bitflags" in node.get("attributes", []):
+                for op in "|&^":
+                    code += f"\n{self.get_indent()}inline {name} operator{op}({name} a, {name} b) {{ return static_cast<{name}>(static_cast<int>(a) {op} static_cast<int>(b)); }}"
+            return code
         if t == "ImportStatement": return f'#include "{node["path"]}.hpp"'
         return f"/* Unknown Node: {t} */"
 
@@ -110,11 +151,12 @@ class Transpiler:
         if isinstance(val, str):
             escaped = val.replace('\\', '\\').replace('"', '\"').replace('\n', '\n').replace('\t', '\t')
             return f'"{escaped}"'
+        if val is None: return "std::nullopt"
         return "true" if val is True else "false" if val is False else str(val)
 
     def gen_program(self, node):
         header = "/**\n * @file output.cpp\n * @brief Generated by Fax-lang Polyglot Compiler\n */\n\n"
-        header += '#include "fax_runtime.hpp"\n#include <cmath>\n'
+        header += '#include "fax_runtime.hpp"\n#include <cmath>\n#include <optional>\n#include <tuple>\n'
         imported = set()
         for stmt in node["body"]:
             if stmt["type"] == "ImportStatement":
@@ -125,13 +167,13 @@ class Transpiler:
         header += "\nnamespace fax_app {\n"
         decls = ""
         for stmt in node["body"]:
-            if stmt["type"] in ["FunctionDeclaration", "StructDeclaration", "VariableDeclaration"]:
+            if stmt["type"] in ["FunctionDeclaration", "StructDeclaration", "EnumDeclaration", "VariableDeclaration"]:
                 decls += self.generate(stmt, 1) + "\n"
         header += decls + "\n} // namespace fax_app\n\nint main(int argc, char* argv[]) {\n    try {\n"
         self.current_indent = 2
         body = ""
         for stmt in node["body"]:
-            if stmt["type"] not in ["FunctionDeclaration", "StructDeclaration", "ImportStatement", "VariableDeclaration"]:
+            if stmt["type"] not in ["FunctionDeclaration", "StructDeclaration", "EnumDeclaration", "ImplBlock", "ImportStatement", "VariableDeclaration"]:
                 if stmt["type"] == "ExpressionStatement" and \
                    stmt["expression"]["type"] == "CallExpression" and \
                    stmt["expression"].get("callee")["name"] == "main": continue
@@ -143,12 +185,16 @@ class Transpiler:
         return header + body + footer
 
     def gen_struct(self, node):
-        code = f"\n{self.get_indent()}struct {self.mangle(node['name'])} {{\n"
+        code = "\n"
+        if node.get("typeParams"):
+            # Literals deduce the arguments from their fields, so `Box{.value = 1}` is a `Box<int>`.
+            code += self.get_indent() + "template <" + ", ".join(f"typename {self.mangle(p)}" for p in node["typeParams"]) + ">\n"
+        code += f"{self.get_indent()}struct {self.mangle(node['name'])} {{\n"
         self.current_indent += 1
         for f in node["fields"]:
             ft = f.get('type') or f.get('field_type')
             code += f"{self.get_indent()}{self.map_type(ft)} {self.mangle(f['name'])};\n"
-        for m in node.get("methods", []): code += self.generate(m) + "\n"
+        for m in node.get("methods", []) + self.impl_methods.get(node["name"], []): code += self.generate(m) + "\n"
         self.current_indent -= 1
         return code + f"{self.get_indent()}}};"
 
@@ -181,13 +227,67 @@ class Transpiler:
         init = f" = {self.generate(node['initializer'], no_paren=True)}" if node.get("initializer") else ""
         return f"{self.get_indent()}{prefix}{self.map_type(dt)} {self.mangle(node['identifier'])}{init};"
 
+    def gen_let_condition(self, node):
+        # `if let x = e` / `while let x = e`: test the optional, then bind its value inside the body.
+        b = node["binding"]
+        holder = f"__fax_opt_{b['name']}"
+        self.enter_scope()
+        self.add_local(b["name"])
+        body = self.generate(node.get("consequent") or node.get("body"), self.current_indent).strip()
+        self.exit_scope()
+        dt = "auto" if b["type"] == "auto" else self.map_type(b["type"])
+        bind = f"{dt} {self.mangle(b['name'])} = *{holder};"
+        inner = " " * ((self.current_indent + 1) * self.indent_size)
+        body = "{\n" + inner + bind + body[1:] if body.startswith("{") else "{ " + bind + " " + body + " }"
+        return f"auto {holder} = {self.generate(node['test'], no_paren=True)}", body
+
+    def null_checked(self, test):
+        if test.get("type") != "BinaryExpression" or test["operator"] != "!=": return None
+        l, r = test["left"], test["right"]
+        if l["type"] == "Identifier" and r["type"] == "Literal" and r["value"] is None: return l["name"]
+        if r["type"] == "Identifier" and l["type"] == "Literal" and l["value"] is None: return r["name"]
+        return None
+
     def gen_if(self, node):
+        if node.get("binding"):
+            cond, cons = self.gen_let_condition(node)
+            alt = f' else {self.generate(node["alternate"], self.current_indent).strip()}' if node.get("alternate") else ""
+            return f"{self.get_indent()}if ({cond}) {cons}{alt}"
         test = self.generate(node['test'], no_paren=True)
+        narrowed = self.null_checked(node["test"])
+        if narrowed: self.narrowed.append(narrowed)
         cons = self.generate(node["consequent"], self.current_indent).strip()
+        if narrowed: self.narrowed.pop()
         alt = f' else {self.generate(node["alternate"], self.current_indent).strip()}' if node.get("alternate") else ""
         return f"{self.get_indent()}if ({test}) {cons}{alt}"
 
+    def gen_match(self, node):
+        # The scrutinee is evaluated once, then each arm becomes a link of an if/else chain.
+        ind = self.get_indent()
+        self.current_indent += 1
+        code = f"{ind}{{\n{self.get_indent()}auto __fax_match = {self.generate(node['discriminant'], no_paren=True)};\n"
+        chain = []
+        for arm in node["arms"]:
+            body = arm["body"]
+            if body["type"] != "BlockStatement": body = {"type": "BlockStatement", "body": [body]}
+            body = self.generate(body, self.current_indent).strip()
+            p = arm["pattern"]
+            if p["type"] == "Identifier" and p["name"] == "_":
+                chain.append(body)
+                break
+            if p["type"] == "MemberExpression" and p["object"]["type"] == "Identifier":
+                pattern = f"{self.mangle(p['object']['name'])}::{self.mangle(p['property'])}"
+            else:
+                pattern = self.generate(p, no_paren=True)
+            chain.append(f"if (__fax_match == {pattern}) {body}")
+        code += self.get_indent() + " else ".join(chain) + "\n"
+        self.current_indent -= 1
+        return code + ind + "}"
+
     def gen_while(self, node):
+        if node.get("binding"):
+            cond, body = self.gen_let_condition(node)
+            return f"{self.get_indent()}while ({cond}) {body}"
         test = self.generate(node['test'], no_paren=True)
         return f"{self.get_indent()}while ({test}) {self.generate(node['body'], self.current_indent).strip()}"
 
@@ -204,13 +304,30 @@ class Transpiler:
         if callee in self.user_symbols and self.current_indent >= 2: return f"fax_app::{callee}({', '.join(args)})"
         return f"{callee}({', '.join(args)})"
 
+    def split_type_args(self, args):
+        # Splits at top-level commas only, so `tuple<int, string>, int` has two parts.
+        parts, depth, start = [], 0, 0
+        for i, ch in enumerate(args):
+            if ch in "<(": depth += 1
+            elif ch in ">)": depth -= 1
+            elif ch == "," and depth == 0:
+                parts.append(args[start:i])
+                start = i + 1
+        return parts + [args[start:]]
+
     def map_type(self, t):
         if not t: return "auto"
         t = t.strip()
         if t.endswith("[]"): return f"fax_std::Array<{self.map_type(t[:-2])}>"
         if t.startswith("ptr<") and t.endswith(">"): return f"fax_std::Ptr<{self.map_type(t[4:-1])}>"
         if t.startswith("ref<") and t.endswith(">"): return f"{self.map_type(t[4:-1])}&"
-        mapping = {"int": "int", "float": "float", "bool": "bool", "string": "std::string", "void": "void"}
+        if t.startswith("optional<") and t.endswith(">"): return f"std::optional<{self.map_type(t[9:-1])}>"
+        if t.startswith("tuple<") and t.endswith(">"):
+            return "std::tuple<" + ", ".join(self.map_type(e) for e in self.split_type_args(t[6:-1])) + ">"
+        base, _, args = t.partition("<")
+        if args.endswith(">") and self.mangle(base) in self.user_symbols:
+            return f"fax_app::{self.mangle(base)}<" + ", ".join(self.map_type(a) for a in self.split_type_args(args[:-1])) + ">"
+        mapping = {"int": "int", "float": "float", "bool": "bool", "string": "std::string", "void": "void", "auto": "auto"}
         m = self.mangle(t)
         return f"fax_app::{m}" if m in self.user_symbols else mapping.get(t, m)
 
@@ -0,0 +1,35 @@
+const FaxCompiler = require('../../../compiler/main');
+
+describe('Diagnostic Renderer', () => {
+  const render = (diag, source) =>
+    FaxCompiler.formatDiagnostic(diag, source.split('\n'), 'main.fax')
+      .join('\n')
+      .replace(/\x1b\[[0-9;]*m/g, '')
+      .split('\n');
+
+  const diag = (label, note) => ({
+    code: 'E0308',
+    message: 'mismatched types',
+    primary_span: { line: 1, column: 9, length: 5, label },
+    secondary_spans: [],
+    note,
+  });
+
+  test('should wrap a long label under the carets', () => {
+    const label = 'expected `int` because of the type annotation on this variable declaration, found a `string` literal here';
+    const lines = render(diag(label, null), 'let x = "abc";');
+    const carets = lines.findIndex(l => l.includes('^^^^^'));
+    expect(lines[carets]).toBe('    |         ^^^^^ expected `int` because of the type annotation on this');
+    expect(lines[carets + 1]).toBe('    |               variable declaration, found a `string` literal here');
+    lines.forEach(l => expect(l.length).toBeLessThanOrEqual(80));
+  });
+
+  test('should prefix every line of a multi-line note and indent wrapped text', () => {
+    const note = 'first line\nconditions are not converted to `bool` implicitly; compare the value instead, e.g. `n != 0`';
+    const lines = render(diag('here', note), 'let x = "abc";');
+    const start = lines.indexOf('  = note: first line');
+    expect(start).toBeGreaterThan(0);
+    expect(lines[start + 1]).toBe('  = note: conditions are not converted to `bool` implicitly; compare the value');
+    expect(lines[start + 2]).toBe('          instead, e.g. `n != 0`');
+  });
+});
@@ -1,5 +1,11 @@
+const { execFileSync } = require('child_process');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
 const { Lexer } = require('../../../compiler/lexer');
-const { Parser } = require('../../../compiler/parser');
+const { Parser } = require('../../../compiler/parser/index.js');
+
+const ROOT = path.resolve(__dirname, '../../..');
 
 describe('Parser Unit Tests', () => {
   const parse = (input) => {
@@ -47,3 +53,80 @@ describe('Parser Unit Tests', () => {
     expect(ast.body[0].expression.arguments.length).toBe(3);
   });
 });
+
+// The compiler pipeline: the Rust lexer's JSON tokens fed to the TypeScript parser,
+// as `compiler/main.js` runs them. A syntax error makes a stage exit non-zero, which throws.
+describe('Pipeline Parser Unit Tests', () => {
+  const parserPath = path.join(ROOT, 'compiler/parser/dist/index.js');
+
+  beforeAll(() => {
+    if (!fs.existsSync(parserPath)) {
+      execFileSync('npx', ['tsc', '-p', 'compiler/parser/tsconfig.json'], { cwd: ROOT });
+    }
+  });
+
+  const parse = (input) => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'fax-parser-'));
+    const sourcePath = path.join(dir, 'input.fax');
+    const tokensPath = path.join(dir, 'tokens.json');
+    fs.writeFileSync(sourcePath, input);
+    const options = { cwd: ROOT, stdio: ['ignore', 'pipe', 'pipe'] };
+    fs.writeFileSync(tokensPath, execFileSync('cargo', ['run', '--quiet', '--manifest-path', 'compiler/lexer/Cargo.toml', '--', sourcePath], options));
+    return JSON.parse(execFileSync('node', [parserPath, tokensPath], options).toString());
+  };
+
+  test('should accept a single trailing comma in lists', () => {
+    const ast = parse('fn add(a: int, b: int,) -> int { return a + b; } add(1, 2,); let xs = [1, 2,];');
+    expect(ast.body[0].params.length).toBe(2);
+    expect(ast.body[1].expression.arguments.length).toBe(2);
+    expect(ast.body[2].initializer.elements.length).toBe(2);
+  });
+
+  test('should decode hex, binary and octal integer literals', () => {
+    const init = parse('let x = 0xFF + 0b101 + 0o17;').body[0].initializer;
+    expect(init.left.left.value).toBe(255);
+    expect(init.left.right.value).toBe(5);
+    expect(init.right.value).toBe(15);
+  });
+
+  test('should parse an if let binding with a call producer', () => {
+    const stmt = parse('if let x: int = next() { println(x); }').body[0];
+    expect(stmt.type).toBe('IfStatement');
+    expect(stmt.binding).toEqual({ name: 'x', type: 'int' });
+    expect(stmt.test.type).toBe('CallExpression');
+    expect(stmt.consequent.type).toBe('BlockStatement');
+  });
+
+  test('should not read a while let body as a struct literal', () => {
+    const stmt = parse('while let item = cursor {}').body[0];
+    expect(stmt.type).toBe('WhileStatement');
+    expect(stmt.binding.type).toBe('auto');
+    expect(stmt.test.type).toBe('Identifier');
+  });
+
+  test('should bind bitwise operators looser than comparisons', () => {
+    const ungrouped = parse('let m = a & b == c;').body[0].initializer;
+    expect(ungrouped.operator).toBe('&');
+    expect(ungrouped.right.operator).toBe('==');
+    const grouped = parse('let m = (a & b) == c;').body[0].initializer;
+    expect(grouped.operator).toBe('==');
+    expect(grouped.left.parenthesized).toBe(true);
+  });
+
+  test('should read T? as an optional type and null as a literal', () => {
+    const decl = parse('let o: int? = null;').body[0];
+    expect(decl.dataType).toBe('optional<int>');
+    expect(decl.initializer).toMatchObject({ type: 'Literal', value: null });
+  });
+
+  test('should parse positional struct initializers', () => {
+    const init = parse('let p = Point { 1, 2 };').body[0].initializer;
+    expect(init.type).toBe('StructLiteral');
+    expect(init.fields.map(f => f.name)).toEqual([null, null]);
+    expect(init.fields[1].value.value).toBe(2);
+  });
+
+  test('should reject a double comma in an argument list', () => {
+    expect(() => parse('f(a,,b);')).toThrow('expected an item, found `,`');
+  });
+});